@@ -0,0 +1,129 @@
+use syn::{Data, DeriveInput, Error, Fields, Generics, Ident, LitStr, Result};
+
+use crate::sql_type::infer_sql_type;
+
+/// Definition of an entity as declared by the struct and its `agrum` attributes.
+pub struct EntityDefinition {
+    /// Name of the struct.
+    pub ident: Ident,
+
+    /// Generics of the struct.
+    pub generics: Generics,
+
+    /// Alias used to prefix the projection fields.
+    pub alias: Option<String>,
+
+    /// Fields of the entity in declaration order.
+    pub fields: Vec<FieldDefinition>,
+}
+
+/// Definition of an entity field.
+pub struct FieldDefinition {
+    /// Name of the Rust field.
+    pub ident: Ident,
+
+    /// Name of the SQL field.
+    pub sql_name: String,
+
+    /// SQL type of the field.
+    pub sql_type: String,
+
+    /// SQL definition of the field in the projection if it is not the field itself.
+    pub projection: Option<String>,
+
+    /// Whether the field is part of the primary key.
+    pub primary_key: bool,
+}
+
+impl EntityDefinition {
+    /// Parse the derive input into an entity definition.
+    pub fn parse(input: &DeriveInput) -> Result<Self> {
+        let fields = match &input.data {
+            Data::Struct(data) => match &data.fields {
+                Fields::Named(fields) => &fields.named,
+                _ => {
+                    return Err(Error::new_spanned(
+                        &input.ident,
+                        "agrum entities must be structs with named fields",
+                    ));
+                }
+            },
+            _ => {
+                return Err(Error::new_spanned(
+                    &input.ident,
+                    "agrum entities must be structs with named fields",
+                ));
+            }
+        };
+        let mut alias = None;
+
+        for attr in input.attrs.iter().filter(|a| a.path().is_ident("agrum")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("alias") {
+                    alias = Some(meta.value()?.parse::<LitStr>()?.value());
+                    Ok(())
+                } else {
+                    Err(meta.error("unknown agrum struct attribute, expected `alias`"))
+                }
+            })?;
+        }
+
+        let fields = fields
+            .iter()
+            .map(|field| {
+                let ident = field.ident.clone().expect("named fields have an identifier");
+                let mut sql_name = None;
+                let mut sql_type = None;
+                let mut projection = None;
+                let mut primary_key = false;
+
+                for attr in field.attrs.iter().filter(|a| a.path().is_ident("agrum")) {
+                    attr.parse_nested_meta(|meta| {
+                        if meta.path.is_ident("rename") {
+                            sql_name = Some(meta.value()?.parse::<LitStr>()?.value());
+                        } else if meta.path.is_ident("sql_type") {
+                            sql_type = Some(meta.value()?.parse::<LitStr>()?.value());
+                        } else if meta.path.is_ident("projection") {
+                            projection = Some(meta.value()?.parse::<LitStr>()?.value());
+                        } else if meta.path.is_ident("primary_key") {
+                            primary_key = true;
+                        } else {
+                            return Err(meta.error(
+                                "unknown agrum field attribute, expected one of `rename`, `sql_type`, `projection`, `primary_key`",
+                            ));
+                        }
+
+                        Ok(())
+                    })?;
+                }
+
+                let sql_type = match sql_type {
+                    Some(sql_type) => sql_type,
+                    None => infer_sql_type(&field.ty).ok_or_else(|| {
+                        Error::new_spanned(
+                            &field.ty,
+                            format!(
+                                "cannot infer the SQL type of field `{ident}`, declare it with #[agrum(sql_type = \"...\")]"
+                            ),
+                        )
+                    })?,
+                };
+
+                Ok(FieldDefinition {
+                    sql_name: sql_name.unwrap_or_else(|| ident.to_string()),
+                    ident,
+                    sql_type,
+                    projection,
+                    primary_key,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            ident: input.ident.clone(),
+            generics: input.generics.clone(),
+            alias,
+            fields,
+        })
+    }
+}
@@ -0,0 +1,198 @@
+#![warn(missing_docs)]
+//! # Agrum derive
+//!
+//! Derive macros for the [Agrum](https://github.com/chanmix51/agrum) database
+//! layer. They generate the `Structured` and `SqlEntity` implementations from
+//! the struct definition so the structure, the projection and the hydration
+//! are declared once. The `ToCopyRow` and `FromCopyRow` implementations let
+//! the entities be copied to and from the database.
+//!
+//! ```rust,ignore
+//! #[derive(SqlEntity, Structured)]
+//! pub struct Company {
+//!     pub company_id: Uuid,
+//!     pub name: String,
+//!     #[agrum(sql_type = "pommr.address")]
+//!     pub default_address: Address,
+//! }
+//! ```
+//!
+//! Struct attributes:
+//!  * `#[agrum(alias = "company")]` prefixes the default projection fields with the given alias.
+//!
+//! Field attributes:
+//!  * `#[agrum(sql_type = "...")]` sets the SQL type instead of inferring it from the Rust type,
+//!  * `#[agrum(rename = "...")]` sets the SQL field name when it differs from the Rust field name,
+//!  * `#[agrum(projection = "...")]` replaces the SQL definition of the field in the projection,
+//!  * `#[agrum(primary_key)]` declares the field as part of the primary key, in declaration order.
+
+mod attributes;
+mod sql_type;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{DeriveInput, parse_macro_input};
+
+use attributes::EntityDefinition;
+
+/// Derive the `Structured` trait.
+/// The structure lists the fields in declaration order with their SQL types.
+#[proc_macro_derive(Structured, attributes(agrum))]
+pub fn derive_structured(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match EntityDefinition::parse(&input) {
+        Ok(definition) => expand_structured(&definition).into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Derive the `SqlEntity` trait.
+/// The projection is the default projection of the structure and the fields
+/// are hydrated by their SQL names.
+#[proc_macro_derive(SqlEntity, attributes(agrum))]
+pub fn derive_sql_entity(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match EntityDefinition::parse(&input) {
+        Ok(definition) => expand_sql_entity(&definition).into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Derive the `ToCopyRow` trait.
+/// The values are the fields in declaration order, which is the order of the
+/// derived structure.
+#[proc_macro_derive(ToCopyRow, attributes(agrum))]
+pub fn derive_to_copy_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match EntityDefinition::parse(&input) {
+        Ok(definition) => expand_to_copy_row(&definition).into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+/// Derive the `FromCopyRow` trait.
+/// The fields are hydrated by their position in declaration order, which is the
+/// order of the derived projection.
+#[proc_macro_derive(FromCopyRow, attributes(agrum))]
+pub fn derive_from_copy_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match EntityDefinition::parse(&input) {
+        Ok(definition) => expand_from_copy_row(&definition).into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn expand_structured(definition: &EntityDefinition) -> proc_macro2::TokenStream {
+    let ident = &definition.ident;
+    let (impl_generics, type_generics, where_clause) = definition.generics.split_for_impl();
+    let fields = definition.fields.iter().map(|field| {
+        let name = &field.sql_name;
+        let sql_type = &field.sql_type;
+
+        quote! { (#name, #sql_type) }
+    });
+    let primary_key: Vec<&String> = definition
+        .fields
+        .iter()
+        .filter(|field| field.primary_key)
+        .map(|field| &field.sql_name)
+        .collect();
+    let primary_key =
+        (!primary_key.is_empty()).then(|| quote! { .with_primary_key(&[#(#primary_key),*]) });
+
+    quote! {
+        impl #impl_generics ::agrum::Structured for #ident #type_generics #where_clause {
+            fn get_structure() -> ::agrum::Structure {
+                ::agrum::Structure::new(&[#(#fields),*])
+                    #primary_key
+            }
+        }
+    }
+}
+
+fn expand_sql_entity(definition: &EntityDefinition) -> proc_macro2::TokenStream {
+    let ident = &definition.ident;
+    let (impl_generics, type_generics, where_clause) = definition.generics.split_for_impl();
+    let alias = definition.alias.as_deref().unwrap_or_default();
+    let definitions = definition.fields.iter().filter_map(|field| {
+        let name = &field.sql_name;
+
+        field
+            .projection
+            .as_ref()
+            .map(|projection| quote! { .set_definition(#name, #projection) })
+    });
+    let hydrations = definition.fields.iter().enumerate().map(|(index, field)| {
+        let field_ident = &field.ident;
+        let name = &field.sql_name;
+
+        quote! {
+            #field_ident: row.try_get(#name).map_err(|error| ::agrum::HydrationError::FieldFetchFailed {
+                error,
+                field_index: #index,
+            })?
+        }
+    });
+
+    quote! {
+        impl #impl_generics ::agrum::SqlEntity for #ident #type_generics #where_clause {
+            fn get_projection() -> ::agrum::Projection<Self> {
+                ::agrum::Projection::<Self>::new(#alias)
+                    #(#definitions)*
+            }
+
+            fn hydrate(row: &::agrum::__private::Row) -> ::std::result::Result<Self, ::agrum::HydrationError> {
+                ::std::result::Result::Ok(Self {
+                    #(#hydrations),*
+                })
+            }
+        }
+    }
+}
+
+fn expand_to_copy_row(definition: &EntityDefinition) -> proc_macro2::TokenStream {
+    let ident = &definition.ident;
+    let (impl_generics, type_generics, where_clause) = definition.generics.split_for_impl();
+    let values = definition.fields.iter().map(|field| {
+        let field_ident = &field.ident;
+
+        quote! { &self.#field_ident as &(dyn ::agrum::__private::ToSql + ::std::marker::Sync) }
+    });
+
+    quote! {
+        impl #impl_generics ::agrum::ToCopyRow for #ident #type_generics #where_clause {
+            fn to_copy_row(&self) -> ::std::vec::Vec<&(dyn ::agrum::__private::ToSql + ::std::marker::Sync)> {
+                ::std::vec![#(#values),*]
+            }
+        }
+    }
+}
+
+fn expand_from_copy_row(definition: &EntityDefinition) -> proc_macro2::TokenStream {
+    let ident = &definition.ident;
+    let (impl_generics, type_generics, where_clause) = definition.generics.split_for_impl();
+    let hydrations = definition.fields.iter().enumerate().map(|(index, field)| {
+        let field_ident = &field.ident;
+
+        quote! {
+            #field_ident: row.try_get(#index).map_err(|error| ::agrum::HydrationError::FieldFetchFailed {
+                error,
+                field_index: #index,
+            })?
+        }
+    });
+
+    quote! {
+        impl #impl_generics ::agrum::FromCopyRow for #ident #type_generics #where_clause {
+            fn from_copy_row(row: &::agrum::__private::BinaryCopyOutRow) -> ::std::result::Result<Self, ::agrum::HydrationError> {
+                ::std::result::Result::Ok(Self {
+                    #(#hydrations),*
+                })
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use syn::{GenericArgument, PathArguments, Type};
+
+/// Infer the SQL type of a Rust type following the `tokio-postgres` type
+/// mapping. Nullable (`Option`) types share the SQL type of their inner type
+/// and `Vec` types are mapped to SQL arrays. It returns `None` when the type
+/// cannot be inferred.
+pub fn infer_sql_type(ty: &Type) -> Option<String> {
+    match ty {
+        Type::Reference(reference) => infer_sql_type(&reference.elem),
+        Type::Path(path) => {
+            let segment = path.path.segments.last()?;
+
+            match segment.ident.to_string().as_str() {
+                "Option" => infer_sql_type(first_type_argument(&segment.arguments)?),
+                "Vec" => {
+                    let inner = first_type_argument(&segment.arguments)?;
+
+                    if is_u8(inner) {
+                        Some("bytea".to_string())
+                    } else {
+                        infer_sql_type(inner).map(|sql_type| format!("{sql_type}[]"))
+                    }
+                }
+                name => scalar_sql_type(name).map(|sql_type| sql_type.to_string()),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn scalar_sql_type(name: &str) -> Option<&'static str> {
+    let sql_type = match name {
+        "bool" => "boolean",
+        "i8" => "\"char\"",
+        "i16" => "smallint",
+        "i32" => "integer",
+        "i64" => "bigint",
+        "u32" => "oid",
+        "f32" => "real",
+        "f64" => "double precision",
+        "String" | "str" => "text",
+        "Uuid" => "uuid",
+        "NaiveDate" => "date",
+        "NaiveTime" => "time",
+        "NaiveDateTime" => "timestamp",
+        "DateTime" | "SystemTime" => "timestamptz",
+        "Value" | "Json" => "jsonb",
+        "IpAddr" => "inet",
+        _ => return None,
+    };
+
+    Some(sql_type)
+}
+
+fn first_type_argument(arguments: &PathArguments) -> Option<&Type> {
+    match arguments {
+        PathArguments::AngleBracketed(arguments) => {
+            arguments.args.iter().find_map(|argument| match argument {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+        }
+        _ => None,
+    }
+}
+
+fn is_u8(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.is_ident("u8"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn infer(ty: &str) -> Option<String> {
+        infer_sql_type(&syn::parse_str::<Type>(ty).unwrap())
+    }
+
+    #[test]
+    fn infer_scalar_types() {
+        assert_eq!(Some("uuid".to_string()), infer("Uuid"));
+        assert_eq!(Some("uuid".to_string()), infer("uuid::Uuid"));
+        assert_eq!(Some("text".to_string()), infer("String"));
+        assert_eq!(Some("text".to_string()), infer("&'static str"));
+        assert_eq!(Some("bigint".to_string()), infer("i64"));
+        assert_eq!(Some("timestamptz".to_string()), infer("DateTime<Utc>"));
+    }
+
+    #[test]
+    fn infer_wrapped_types() {
+        assert_eq!(Some("text".to_string()), infer("Option<String>"));
+        assert_eq!(Some("integer[]".to_string()), infer("Vec<i32>"));
+        assert_eq!(Some("uuid[]".to_string()), infer("Option<Vec<Uuid>>"));
+        assert_eq!(Some("bytea".to_string()), infer("Vec<u8>"));
+    }
+
+    #[test]
+    fn infer_unknown_type() {
+        assert_eq!(None, infer("Address"));
+        assert_eq!(None, infer("Option<Address>"));
+        assert_eq!(None, infer("(i32, i32)"));
+    }
+}
@@ -0,0 +1,78 @@
+use std::{hint::black_box, iter::repeat_n, sync::LazyLock};
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+
+use agrum::{CompiledTemplate, SqlCommand};
+
+const TEMPLATE: &str =
+    "insert into {:source:} ({:structure:}) values {:values:} returning {:projection:}";
+
+static COMPILED: LazyLock<CompiledTemplate> = LazyLock::new(|| CompiledTemplate::new(TEMPLATE));
+
+/// Return the `values` of a multi-rows insert of 5 columns.
+fn values(rows: usize) -> String {
+    repeat_n("($?, $?, $?, $?, $?)", rows)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Render the template the way it was done before the templates were
+/// compiled: one replace per variable then one replace per placeholder.
+fn render_with_replace(variables: &[(&str, &str)]) -> String {
+    let mut query = TEMPLATE.to_string();
+
+    for (name, value) in variables {
+        query = query.replace(&format!("{{:{name}:}}"), value);
+    }
+
+    let mut param_index = 1;
+    while query.contains("$?") {
+        query = query.replacen("$?", &format!("${param_index}"), 1);
+        param_index += 1;
+    }
+
+    query
+}
+
+fn render(command: &mut SqlCommand<'_>, variables: &[(&'static str, &str)]) -> String {
+    for (name, value) in variables {
+        command.set_variable(name, value);
+    }
+
+    command.to_string()
+}
+
+fn bench_render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render");
+
+    for rows in [10, 100, 1_000] {
+        let values = values(rows);
+        let variables = [
+            ("source", "pommr.contact"),
+            (
+                "structure",
+                "contact_id, name, email, phone_number, company_id",
+            ),
+            ("values", values.as_str()),
+            (
+                "projection",
+                "contact_id, name, email, phone_number, company_id",
+            ),
+        ];
+
+        group.bench_with_input(BenchmarkId::new("replace", rows), &variables, |b, v| {
+            b.iter(|| render_with_replace(black_box(v)))
+        });
+        group.bench_with_input(BenchmarkId::new("parse", rows), &variables, |b, v| {
+            b.iter(|| render(&mut SqlCommand::new(TEMPLATE), black_box(v)))
+        });
+        group.bench_with_input(BenchmarkId::new("compiled", rows), &variables, |b, v| {
+            b.iter(|| render(&mut SqlCommand::from_template(&COMPILED), black_box(v)))
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_render);
+criterion_main!(benches);
@@ -0,0 +1,631 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fmt::{Display, Write},
+    sync::Arc,
+};
+
+use crate::{
+    CompiledTemplate, ConstraintErrorMap, Result, TemplateError, ToSqlAny,
+    template::{Segment, value_segments},
+};
+
+/// A SQL command builder.
+/// It uses the same templating system as [SqlQuery](crate::SqlQuery) but it is
+/// not bound to an entity type, it is meant for the statements whose returned
+/// rows are not used like `delete`, `update` or DDL statements.
+/// The variables are enclosed in `{:variable:}` placeholders and the
+/// parameters are expanded in the `$?` placeholders.
+/// Named parameters are expanded in the `$:name` placeholders, a named
+/// parameter is bound once and can appear several times. Positional and named
+/// placeholders are numbered in order of first appearance.
+/// Variables are substituted in a single pass: a value is never expanded again
+/// but the parameter placeholders it holds are numbered where it lands. Use
+/// [SqlCommand::try_expand] to check the template before sending it.
+/// Fragments are variables that carry their own parameters, like sub-queries.
+/// Their parameters are merged at the place the fragment appears in the
+/// template.
+///
+/// # Examples
+/// ```rust
+/// use agrum::{SqlCommand, params};
+///
+/// let mut command = SqlCommand::new("delete from {:source:} where created_at < $?");
+/// command
+///     .set_variable("source", "pommr.event")
+///     .set_parameters(params!["2024-01-01"]);
+///
+/// assert_eq!("delete from pommr.event where created_at < $1", command.to_string());
+///
+/// let company_id = 1_i32;
+/// let mut command = SqlCommand::new(
+///     "update company set parent_id = $:company_id where company_id <> $:company_id and name = $?",
+/// );
+/// command
+///     .set_named_parameter("company_id", &company_id)
+///     .add_parameter(&"whatever");
+///
+/// assert_eq!(
+///     "update company set parent_id = $1 where company_id <> $1 and name = $2",
+///     command.to_string()
+/// );
+/// ```
+pub struct SqlCommand<'a> {
+    template: Cow<'a, CompiledTemplate>,
+    parameters: Vec<&'a dyn ToSqlAny>,
+    named_parameters: HashMap<&'a str, &'a dyn ToSqlAny>,
+    variables: HashMap<&'a str, String>,
+    fragments: HashMap<&'a str, (String, Vec<&'a dyn ToSqlAny>)>,
+    constraint_error_map: Option<Arc<ConstraintErrorMap>>,
+}
+
+impl<'a> SqlCommand<'a> {
+    /// Create a new command using the given string as a SQL template.
+    pub fn new(query: &str) -> Self {
+        Self::with_template(Cow::Owned(CompiledTemplate::new(query)))
+    }
+
+    /// Create a new command from a compiled template, the template is not
+    /// parsed again.
+    pub fn from_template(template: &'a CompiledTemplate) -> Self {
+        Self::with_template(Cow::Borrowed(template))
+    }
+
+    fn with_template(template: Cow<'a, CompiledTemplate>) -> Self {
+        Self {
+            template,
+            parameters: Vec::new(),
+            named_parameters: HashMap::new(),
+            variables: HashMap::new(),
+            fragments: HashMap::new(),
+            constraint_error_map: None,
+        }
+    }
+
+    /// Set a variable in the command. This variable will be replaced by its
+    /// value in the command.
+    pub fn set_variable(&mut self, name: &'a str, value: &str) -> &mut Self {
+        self.fragments.remove(name);
+        self.variables.insert(name, value.to_string());
+        self
+    }
+
+    /// Set a variable whose value holds its own `$?` parameters. The
+    /// parameters are merged with the parameters of the command at the place
+    /// the variable appears in the template.
+    pub fn set_fragment(
+        &mut self,
+        name: &'a str,
+        value: &str,
+        parameters: Vec<&'a dyn ToSqlAny>,
+    ) -> &mut Self {
+        self.variables.remove(name);
+        self.fragments.insert(name, (value.to_string(), parameters));
+        self
+    }
+
+    /// Set a variable to the given sub-query, its parameters are merged with
+    /// the parameters of the command at the place the variable appears in the
+    /// template.
+    ///
+    /// ```rust
+    /// use agrum::{SqlCommand, params};
+    ///
+    /// let mut contacts = SqlCommand::new("select company_id from contact where email like $?");
+    /// contacts.add_parameter(&"%@example.com");
+    /// let mut command = SqlCommand::new(
+    ///     "update company set flagged = $? where company_id in ({:contacts:}) and name <> $?",
+    /// );
+    /// command
+    ///     .set_subquery("contacts", contacts)
+    ///     .set_parameters(params![true, "root"]);
+    ///
+    /// assert_eq!(
+    ///     "update company set flagged = $1 where company_id in (select company_id from contact where email like $2) and name <> $3",
+    ///     command.to_string()
+    /// );
+    /// ```
+    pub fn set_subquery(&mut self, name: &'a str, query: impl Into<SqlCommand<'a>>) -> &mut Self {
+        let (query, parameters) = query.into().expand_positional();
+
+        self.set_fragment(name, &query, parameters)
+    }
+
+    /// Add a parameter to the command. The parameter will be expanded in the
+    /// `$?` placeholder.
+    pub fn add_parameter(&mut self, parameter: &'a dyn ToSqlAny) -> &mut Self {
+        self.parameters.push(parameter);
+        self
+    }
+
+    /// Append a vec of parameters to the command.
+    pub fn append_parameters(&mut self, parameters: Vec<&'a dyn ToSqlAny>) -> &mut Self {
+        self.parameters.extend(parameters);
+        self
+    }
+
+    /// Set the parameters of the command.
+    pub fn set_parameters(&mut self, parameters: Vec<&'a dyn ToSqlAny>) -> &mut Self {
+        self.parameters = parameters;
+        self
+    }
+
+    /// Bind a value to the named parameter. It will be expanded in all the
+    /// `$:name` placeholders.
+    pub fn set_named_parameter(&mut self, name: &'a str, parameter: &'a dyn ToSqlAny) -> &mut Self {
+        self.named_parameters.insert(name, parameter);
+        self
+    }
+
+    /// Set the map used to turn the database errors raised by this command into
+    /// domain errors.
+    pub fn set_constraint_error_map(
+        &mut self,
+        constraint_error_map: Option<Arc<ConstraintErrorMap>>,
+    ) -> &mut Self {
+        self.constraint_error_map = constraint_error_map;
+        self
+    }
+
+    /// Return the map used to turn the database errors into domain errors.
+    pub fn get_constraint_error_map(&self) -> Option<&Arc<ConstraintErrorMap>> {
+        self.constraint_error_map.as_ref()
+    }
+
+    /// Return the variables of the command.
+    pub fn get_variables(&self) -> &HashMap<&'a str, String> {
+        &self.variables
+    }
+
+    /// Return the positional parameters of the command. This method is mostly
+    /// intended for testing purposes since the parameters are cloned.
+    pub fn get_parameters(&self) -> Vec<&'a dyn ToSqlAny> {
+        self.parameters.clone()
+    }
+
+    /// Return the named parameters of the command.
+    pub fn get_named_parameters(&self) -> &HashMap<&'a str, &'a dyn ToSqlAny> {
+        &self.named_parameters
+    }
+
+    /// Return the template of the command.
+    pub(crate) fn get_template(&self) -> &CompiledTemplate {
+        &self.template
+    }
+
+    /// Return the fragments of the command and their parameters.
+    pub(crate) fn get_fragments(&self) -> &HashMap<&'a str, (String, Vec<&'a dyn ToSqlAny>)> {
+        &self.fragments
+    }
+
+    /// Return the command and the parameters to be sent to the server, in the
+    /// order of their placeholders.
+    /// This consumes the command instance.
+    pub fn expand(self) -> (String, Vec<&'a dyn ToSqlAny>) {
+        let rendering = self.render();
+
+        (rendering.query, rendering.parameters)
+    }
+
+    /// Return the command and the parameters to be sent to the server like
+    /// [SqlCommand::expand] but check the template first. An
+    /// [Error::Template](crate::Error::Template) is returned if a placeholder has no value, if a
+    /// variable or a named parameter is not used or if the number of `$?`
+    /// placeholders differs from the number of parameters.
+    pub fn try_expand(self) -> Result<(String, Vec<&'a dyn ToSqlAny>)> {
+        self.try_expand_with_optional(&[])
+    }
+
+    /// Return the command and its parameters with `$?` placeholders so it can
+    /// be embedded in another query, the named parameters being bound at each
+    /// of their placeholders.
+    pub(crate) fn expand_positional(self) -> (String, Vec<&'a dyn ToSqlAny>) {
+        let rendering = self.render_with(false);
+
+        (rendering.query, rendering.parameters)
+    }
+
+    /// Strictly expand the command, the given variables may be left unused.
+    pub(crate) fn try_expand_with_optional(
+        self,
+        optional_variables: &[&str],
+    ) -> Result<(String, Vec<&'a dyn ToSqlAny>)> {
+        let rendering = self.render();
+
+        if let Some(name) = rendering.unresolved_variables.first() {
+            return Err(TemplateError::UnresolvedVariable(name.to_string()).into());
+        }
+
+        if let Some(name) = rendering.unresolved_parameters.first() {
+            return Err(TemplateError::UnresolvedParameter(name.to_string()).into());
+        }
+
+        let mut unused_variables: Vec<&str> = self
+            .variables
+            .keys()
+            .chain(self.fragments.keys())
+            .filter(|name| {
+                !rendering.used_variables.contains(*name) && !optional_variables.contains(name)
+            })
+            .copied()
+            .collect();
+        unused_variables.sort_unstable();
+
+        if let Some(name) = unused_variables.first() {
+            return Err(TemplateError::UnusedVariable(name.to_string()).into());
+        }
+
+        let mut unused_parameters: Vec<&str> = self
+            .named_parameters
+            .keys()
+            .filter(|name| !rendering.used_parameters.contains(*name))
+            .copied()
+            .collect();
+        unused_parameters.sort_unstable();
+
+        if let Some(name) = unused_parameters.first() {
+            return Err(TemplateError::UnusedParameter(name.to_string()).into());
+        }
+
+        let parameters = self.parameters.len() + rendering.fragment_parameters;
+
+        if rendering.placeholders != parameters {
+            return Err(TemplateError::ParameterCount {
+                placeholders: rendering.placeholders,
+                parameters,
+            }
+            .into());
+        }
+
+        Ok((rendering.query, rendering.parameters))
+    }
+
+    /// Render the template in a single pass. The variables are replaced by
+    /// their values without expanding the variables they may contain, but the
+    /// parameter placeholders of the values are numbered in order. The
+    /// placeholders of the fragments take the parameters of the fragment. The
+    /// parameters are returned in placeholder order, the parameters not
+    /// consumed by a placeholder are appended.
+    /// Placeholders without value are left as is.
+    fn render(&self) -> Rendering<'_, 'a> {
+        self.render_with(true)
+    }
+
+    /// Render the template, the parameter placeholders being numbered or left
+    /// as `$?`.
+    fn render_with(&self, numbered: bool) -> Rendering<'_, 'a> {
+        let mut rendering = Rendering {
+            numbered,
+            query: String::with_capacity(self.template.get_template().len()),
+            parameters: Vec::with_capacity(self.parameters.len()),
+            placeholders: 0,
+            fragment_parameters: 0,
+            leftovers: Vec::new(),
+            named_indexes: HashMap::new(),
+            unresolved_variables: Vec::new(),
+            unresolved_parameters: Vec::new(),
+            used_variables: HashSet::new(),
+            used_parameters: HashSet::new(),
+        };
+        let mut positional = self.parameters.iter();
+
+        for segment in self.template.segments() {
+            let Segment::Variable(name) = segment else {
+                rendering.push(segment, self, &mut positional);
+                continue;
+            };
+
+            if let Some((name, (value, parameters))) = self.fragments.get_key_value(name) {
+                let mut fragment_positional = parameters.iter();
+                rendering.used_variables.insert(name);
+                rendering.fragment_parameters += parameters.len();
+
+                for segment in value_segments(value) {
+                    rendering.push(segment, self, &mut fragment_positional);
+                }
+                rendering.leftovers.extend(fragment_positional);
+            } else if let Some((name, value)) = self.variables.get_key_value(name) {
+                rendering.used_variables.insert(name);
+
+                for segment in value_segments(value) {
+                    rendering.push(segment, self, &mut positional);
+                }
+            } else {
+                rendering.unresolved_variables.push(name);
+                let _ = write!(rendering.query, "{{:{name}:}}");
+            }
+        }
+        rendering.parameters.extend(positional);
+        let leftovers = std::mem::take(&mut rendering.leftovers);
+        rendering.parameters.extend(leftovers);
+
+        rendering
+    }
+}
+
+impl Display for SqlCommand<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.render().query)
+    }
+}
+
+/// The rendered template and what has been found while rendering it.
+struct Rendering<'s, 'a> {
+    numbered: bool,
+    query: String,
+    parameters: Vec<&'a dyn ToSqlAny>,
+    placeholders: usize,
+    fragment_parameters: usize,
+    leftovers: Vec<&'a dyn ToSqlAny>,
+    named_indexes: HashMap<&'s str, usize>,
+    unresolved_variables: Vec<&'s str>,
+    unresolved_parameters: Vec<&'s str>,
+    used_variables: HashSet<&'s str>,
+    used_parameters: HashSet<&'s str>,
+}
+
+impl<'s, 'a> Rendering<'s, 'a> {
+    /// Render a segment, parameter placeholders are numbered in order of first
+    /// appearance unless the rendering is not numbered.
+    fn push(
+        &mut self,
+        segment: Segment<'s>,
+        command: &'s SqlCommand<'a>,
+        positional: &mut std::slice::Iter<'s, &'a dyn ToSqlAny>,
+    ) {
+        match segment {
+            Segment::Literal(text) => self.query.push_str(text),
+            Segment::Variable(name) => {
+                let _ = write!(self.query, "{{:{name}:}}");
+            }
+            Segment::Parameter => {
+                self.placeholders += 1;
+                self.parameters.extend(positional.next());
+
+                if self.numbered {
+                    let index = self.named_indexes.len() + self.placeholders;
+                    let _ = write!(self.query, "${index}");
+                } else {
+                    self.query.push_str("$?");
+                }
+            }
+            Segment::NamedParameter(name) => match command.named_parameters.get_key_value(name) {
+                Some((name, parameter)) if !self.numbered => {
+                    self.parameters.push(*parameter);
+                    self.used_parameters.insert(name);
+                    self.query.push_str("$?");
+                }
+                Some((name, parameter)) => {
+                    let next_index = self.named_indexes.len() + self.placeholders + 1;
+                    let index = *self.named_indexes.entry(name).or_insert_with(|| {
+                        self.parameters.push(*parameter);
+                        next_index
+                    });
+                    self.used_parameters.insert(name);
+                    let _ = write!(self.query, "${index}");
+                }
+                None => {
+                    self.unresolved_parameters.push(name);
+                    let _ = write!(self.query, "$:{name}");
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use crate::params;
+
+    use super::*;
+
+    #[test]
+    fn test_no_default_variable() {
+        let command = SqlCommand::new("truncate {:projection:}");
+
+        assert!(command.get_variables().is_empty());
+        assert_eq!("truncate {:projection:}", command.to_string());
+    }
+
+    #[test]
+    fn test_expand() {
+        let mut command = SqlCommand::new("delete from {:source:} where {:condition:}");
+        command
+            .set_variable("source", "some_table")
+            .set_variable("condition", "id = $? or parent_id = $?")
+            .set_parameters(params![1_i32])
+            .add_parameter(&2_i32);
+        let (query, parameters) = command.expand();
+
+        assert_eq!(
+            "delete from some_table where id = $1 or parent_id = $2",
+            query
+        );
+        assert_eq!(parameters.len(), 2);
+        let parameter: &i32 = (parameters[1] as &dyn Any).downcast_ref().unwrap();
+        assert_eq!(parameter, &2_i32);
+    }
+
+    #[test]
+    fn test_named_parameters() {
+        let mut command =
+            SqlCommand::new("select $:b, $?, $:a, $:b, $:unknown, $:a::text, $? from {:source:}");
+        command
+            .set_variable("source", "some_table where c = $:c")
+            .set_named_parameter("a", &1_i32)
+            .set_named_parameter("b", &2_i32)
+            .set_named_parameter("c", &3_i32)
+            .set_named_parameter("unused", &4_i32)
+            .set_parameters(params![10_i32, 20_i32]);
+        let (query, parameters) = command.expand();
+
+        assert_eq!(
+            "select $1, $2, $3, $1, $:unknown, $3::text, $4 from some_table where c = $5",
+            query
+        );
+        let parameters: Vec<i32> = parameters
+            .into_iter()
+            .map(|p| *(p as &dyn Any).downcast_ref::<i32>().unwrap())
+            .collect();
+        assert_eq!(vec![2, 10, 1, 20, 3], parameters);
+    }
+
+    #[test]
+    fn test_dollar_signs_are_kept() {
+        let command = SqlCommand::new("select $$text$$, $1, $: from t");
+
+        assert_eq!("select $$text$$, $1, $: from t", command.to_string());
+    }
+
+    #[test]
+    fn test_variables_are_not_expanded_recursively() {
+        let mut command = SqlCommand::new("select {:a:}, $? from {:b:}");
+        command
+            .set_variable("a", "'{:b:}'")
+            .set_variable("b", "t where c = $?")
+            .set_parameters(params![1_i32, 2_i32]);
+
+        assert_eq!(
+            "select '{:b:}', $1 from t where c = $2",
+            command.to_string()
+        );
+    }
+
+    #[test]
+    fn test_try_expand() {
+        let mut command = SqlCommand::new("delete from {:source:} where {:condition:}");
+        command
+            .set_variable("source", "some_table")
+            .set_variable("condition", "id = $:id or parent_id = $?")
+            .set_named_parameter("id", &1_i32)
+            .set_parameters(params![2_i32]);
+        let (query, parameters) = command.try_expand().unwrap();
+
+        assert_eq!(
+            "delete from some_table where id = $1 or parent_id = $2",
+            query
+        );
+        assert_eq!(parameters.len(), 2);
+    }
+
+    fn template_error(command: SqlCommand<'_>) -> TemplateError {
+        match command.try_expand() {
+            Err(crate::Error::Template(error)) => error,
+            _ => panic!("a template error was expected"),
+        }
+    }
+
+    #[test]
+    fn test_try_expand_errors() {
+        let command = SqlCommand::new("delete from {:source:}");
+        assert_eq!(
+            TemplateError::UnresolvedVariable("source".to_string()),
+            template_error(command)
+        );
+
+        let mut command = SqlCommand::new("delete from some_table");
+        command.set_variable("source", "some_table");
+        assert_eq!(
+            TemplateError::UnusedVariable("source".to_string()),
+            template_error(command)
+        );
+
+        let command = SqlCommand::new("delete from some_table where id = $:id");
+        assert_eq!(
+            TemplateError::UnresolvedParameter("id".to_string()),
+            template_error(command)
+        );
+
+        let mut command = SqlCommand::new("delete from some_table");
+        command.set_named_parameter("id", &1_i32);
+        assert_eq!(
+            TemplateError::UnusedParameter("id".to_string()),
+            template_error(command)
+        );
+
+        let mut command = SqlCommand::new("delete from some_table where id = $? or id = $?");
+        command.set_parameters(params![1_i32]);
+        assert_eq!(
+            TemplateError::ParameterCount {
+                placeholders: 2,
+                parameters: 1
+            },
+            template_error(command)
+        );
+
+        let mut command = SqlCommand::new("delete from some_table");
+        command.set_parameters(params![1_i32]);
+        assert_eq!(
+            TemplateError::ParameterCount {
+                placeholders: 0,
+                parameters: 1
+            },
+            template_error(command)
+        );
+    }
+
+    #[test]
+    fn test_expand_positional() {
+        let mut command =
+            SqlCommand::new("select 1 from {:source:} where a = $:a and b = $? or c = $:a");
+        command
+            .set_variable("source", "some_table where d = $?")
+            .set_named_parameter("a", &1_i32)
+            .set_parameters(params![2_i32, 3_i32]);
+        let (query, parameters) = command.expand_positional();
+
+        assert_eq!(
+            "select 1 from some_table where d = $? where a = $? and b = $? or c = $?",
+            query
+        );
+        let parameters: Vec<i32> = parameters
+            .into_iter()
+            .map(|p| *(p as &dyn Any).downcast_ref::<i32>().unwrap())
+            .collect();
+        assert_eq!(vec![2, 1, 3, 1], parameters);
+    }
+
+    #[test]
+    fn test_subquery() {
+        let mut subquery =
+            SqlCommand::new("select id from {:source:} where a = $:a or b = $:a and c = $?");
+        subquery
+            .set_variable("source", "other_table")
+            .set_named_parameter("a", &1_i32)
+            .add_parameter(&2_i32);
+        let mut command = SqlCommand::new(
+            "select $? from some_table where d = $:d and id in ({:ids:}) and e = $?",
+        );
+        command
+            .set_subquery("ids", subquery)
+            .set_named_parameter("d", &3_i32)
+            .set_parameters(params![10_i32, 20_i32]);
+        let (query, parameters) = command.try_expand().unwrap();
+
+        assert_eq!(
+            "select $1 from some_table where d = $2 and id in (select id from other_table where a = $3 or b = $4 and c = $5) and e = $6",
+            query
+        );
+        let parameters: Vec<i32> = parameters
+            .into_iter()
+            .map(|p| *(p as &dyn Any).downcast_ref::<i32>().unwrap())
+            .collect();
+        assert_eq!(vec![10, 3, 1, 1, 2, 20], parameters);
+    }
+
+    #[test]
+    fn test_fragment_parameter_count() {
+        let mut command = SqlCommand::new("select 1 from t where {:condition:}");
+        command.set_fragment("condition", "a = $? and b = $?", params![1_i32]);
+
+        assert!(matches!(
+            command.try_expand(),
+            Err(crate::Error::Template(TemplateError::ParameterCount {
+                placeholders: 2,
+                parameters: 1
+            }))
+        ));
+    }
+}
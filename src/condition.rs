@@ -1,12 +1,23 @@
-use std::{fmt::Display, iter::repeat_n};
+use std::{fmt::Display, iter::repeat_n, ops::Not};
 
 use tokio_postgres::types::ToSql;
 
+use crate::SqlCommand;
+
 /// A trait to mark types that can be converted to a `ToSql` type and also
 /// implement `Any` and `Sync`. This trait is used for the parameters of the
-/// queries.
-pub trait ToSqlAny: ToSql + std::any::Any + Sync {}
-impl<T: ToSql + std::any::Any + Sync> ToSqlAny for T {}
+/// queries. The parameters are cloned when a query is turned into an owned
+/// query.
+pub trait ToSqlAny: ToSql + std::any::Any + Sync {
+    /// Return a copy of the parameter owned by a boxed value.
+    fn to_owned_parameter(&self) -> OwnedParameter;
+}
+
+impl<T: ToSql + Clone + Send + Sync + 'static> ToSqlAny for T {
+    fn to_owned_parameter(&self) -> OwnedParameter {
+        Box::new(self.clone())
+    }
+}
 
 /// A macro to create a vector of parameters. This macro is used to create the
 /// parameters of the queries.
@@ -22,12 +33,14 @@ macro_rules! params {
 
 /// A structure to hold the boolean conditions of the queries.
 /// It is used to implement the precedence of the boolean logic operators.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 enum BooleanCondition {
+    #[default]
     None,
     Expression(String),
     And(Box<BooleanCondition>, Box<BooleanCondition>),
     Or(Box<BooleanCondition>, Box<BooleanCondition>),
+    Not(Box<BooleanCondition>),
 }
 
 impl BooleanCondition {
@@ -42,6 +55,7 @@ impl BooleanCondition {
                 (false, false) => format!("{} and {}", lft.expand(), rgt.expand()),
             },
             Self::Or(lft, rgt) => format!("{} or {}", lft.expand(), rgt.expand()),
+            Self::Not(condition) => format!("not ({})", condition.expand()),
         }
     }
 
@@ -141,43 +155,331 @@ impl<'a> WhereCondition<'a> {
         }
     }
 
+    /// Create a new condition with a `NOT IN` SQL expression and the
+    /// parameters. It creates as many `$?` placeholders as the number of
+    /// parameters.
+    pub fn where_not_in(field: &str, parameters: Vec<&'a dyn ToSqlAny>) -> Self {
+        Self::where_in(field, parameters).not()
+    }
+
+    /// Create a new condition checking the field is null.
+    pub fn is_null(field: &str) -> Self {
+        Self::new(&format!("{field} is null"), Vec::new())
+    }
+
+    /// Create a new condition checking the field is not null.
+    pub fn is_not_null(field: &str) -> Self {
+        Self::new(&format!("{field} is not null"), Vec::new())
+    }
+
+    /// Create a new condition checking the field is between the two bounds,
+    /// bounds included.
+    pub fn between(field: &str, low: &'a dyn ToSqlAny, high: &'a dyn ToSqlAny) -> Self {
+        Self::new(&format!("{field} between $? and $?"), vec![low, high])
+    }
+
+    /// Create a new condition checking the field is equal to any element of
+    /// the given array. Unlike [WhereCondition::where_in], the array is sent as
+    /// a single parameter whatever its size.
+    ///
+    /// ```rust
+    /// use agrum::WhereCondition;
+    ///
+    /// let ids = vec![1_i32, 2, 3];
+    /// let condition = WhereCondition::any("id", &ids);
+    /// assert_eq!("id = any($?)", condition.to_string());
+    /// ```
+    pub fn any(field: &str, values: &'a dyn ToSqlAny) -> Self {
+        Self::new(&format!("{field} = any($?)"), vec![values])
+    }
+
+    /// Create a new condition matching the field against a `LIKE` pattern.
+    /// Use [escape_like] to match user provided text literally.
+    pub fn like(field: &str, pattern: &'a dyn ToSqlAny) -> Self {
+        Self::new(&format!("{field} like $?"), vec![pattern])
+    }
+
+    /// Create a new condition matching the field against a case insensitive
+    /// `ILIKE` pattern. Use [escape_like] to match user provided text
+    /// literally.
+    pub fn ilike(field: &str, pattern: &'a dyn ToSqlAny) -> Self {
+        Self::new(&format!("{field} ilike $?"), vec![pattern])
+    }
+
+    /// Create a new condition checking the given sub-query returns at least
+    /// one row. The parameters of the sub-query are kept with the condition.
+    ///
+    /// ```rust
+    /// use agrum::{SqlCommand, WhereCondition};
+    ///
+    /// let company_id = 1_i32;
+    /// let mut contacts = SqlCommand::new("select 1 from contact where contact.company_id = $?");
+    /// contacts.add_parameter(&company_id);
+    /// let condition = WhereCondition::exists(contacts);
+    /// assert_eq!(
+    ///     "exists (select 1 from contact where contact.company_id = $?)",
+    ///     condition.to_string()
+    /// );
+    /// ```
+    pub fn exists(query: impl Into<SqlCommand<'a>>) -> Self {
+        let (query, parameters) = query.into().expand_positional();
+
+        Self::new(&format!("exists ({query})"), parameters)
+    }
+
+    /// Create a new condition checking the field is one of the values returned
+    /// by the given sub-query. The parameters of the sub-query are kept with
+    /// the condition.
+    pub fn in_subquery(field: &str, query: impl Into<SqlCommand<'a>>) -> Self {
+        let (query, parameters) = query.into().expand_positional();
+
+        Self::new(&format!("{field} in ({query})"), parameters)
+    }
+
+    /// Turn the condition into an [OwnedWhereCondition] of the same structure,
+    /// the parameters being cloned.
+    ///
+    /// ```rust
+    /// use agrum::{OwnedWhereCondition, WhereCondition, params};
+    ///
+    /// fn in_city(city: String) -> OwnedWhereCondition {
+    ///     WhereCondition::new("city = $?", params![city])
+    ///         .or_where(WhereCondition::is_null("city"))
+    ///         .into_owned()
+    /// }
+    ///
+    /// let condition = in_city("Paris".to_string());
+    /// assert_eq!("city = $? or city is null", condition.to_string());
+    /// ```
+    pub fn into_owned(self) -> OwnedWhereCondition {
+        OwnedWhereCondition {
+            condition: self.condition,
+            parameters: self
+                .parameters
+                .into_iter()
+                .map(|parameter| parameter.to_owned_parameter())
+                .collect(),
+        }
+    }
+
     /// Compose the condition with a `AND` boolean logic operator.
-    pub fn and_where(mut self, mut condition: WhereCondition<'a>) -> Self {
-        if condition.condition.is_none() {
-            return self;
+    pub fn and_where(self, condition: WhereCondition<'a>) -> Self {
+        let (condition, parameters) = compose(
+            (self.condition, self.parameters),
+            (condition.condition, condition.parameters),
+            BooleanCondition::And,
+        );
+
+        Self {
+            condition,
+            parameters,
         }
+    }
+
+    /// Compose the condition with a `OR` boolean logic operator.
+    pub fn or_where(self, condition: WhereCondition<'a>) -> Self {
+        let (condition, parameters) = compose(
+            (self.condition, self.parameters),
+            (condition.condition, condition.parameters),
+            BooleanCondition::Or,
+        );
 
-        if self.condition.is_none() {
-            self.condition = condition.condition;
-            self.parameters = condition.parameters;
-        } else {
-            let temp = BooleanCondition::None;
-            let my_condition = std::mem::replace(&mut self.condition, temp);
-            self.condition =
-                BooleanCondition::And(Box::new(my_condition), Box::new(condition.condition));
-            self.parameters.append(&mut condition.parameters);
+        Self {
+            condition,
+            parameters,
         }
+    }
+}
+
+impl Not for WhereCondition<'_> {
+    type Output = Self;
 
-        self
+    /// Negate the condition, it is enclosed in parentheses so the negation
+    /// applies to the whole condition.
+    fn not(self) -> Self {
+        Self {
+            condition: BooleanCondition::Not(Box::new(self.condition)),
+            parameters: self.parameters,
+        }
     }
+}
 
-    /// Compose the condition with a `OR` boolean logic operator.
-    pub fn or_where(mut self, mut condition: WhereCondition<'a>) -> Self {
-        if condition.condition.is_none() {
-            return self;
+impl<'a> From<&'a OwnedWhereCondition> for WhereCondition<'a> {
+    /// Borrow the parameters of the owned condition.
+    fn from(condition: &'a OwnedWhereCondition) -> Self {
+        Self {
+            condition: condition.condition.clone(),
+            parameters: condition
+                .parameters
+                .iter()
+                .map(|parameter| parameter.as_ref() as &dyn ToSqlAny)
+                .collect(),
         }
-        if self.condition.is_none() {
-            self.condition = condition.condition;
-            self.parameters = condition.parameters;
-        } else {
-            let temp = BooleanCondition::None;
-            let my_condition = std::mem::replace(&mut self.condition, temp);
-            self.condition =
-                BooleanCondition::Or(Box::new(my_condition), Box::new(condition.condition));
-            self.parameters.append(&mut condition.parameters);
+    }
+}
+
+/// Compose two conditions with the given operator, a `None` condition being
+/// neutral. The parameters are kept in the order of the conditions.
+fn compose<P>(
+    (left, mut left_parameters): (BooleanCondition, Vec<P>),
+    (right, mut right_parameters): (BooleanCondition, Vec<P>),
+    operator: fn(Box<BooleanCondition>, Box<BooleanCondition>) -> BooleanCondition,
+) -> (BooleanCondition, Vec<P>) {
+    if right.is_none() {
+        return (left, left_parameters);
+    }
+
+    if left.is_none() {
+        return (right, right_parameters);
+    }
+    left_parameters.append(&mut right_parameters);
+
+    (operator(Box::new(left), Box::new(right)), left_parameters)
+}
+
+/// Escape the `LIKE` wildcards `%` and `_` and the escape character of the
+/// given text so it is matched literally by a `LIKE` or `ILIKE` pattern.
+///
+/// ```rust
+/// use agrum::{WhereCondition, escape_like};
+///
+/// let pattern = format!("{}%", escape_like("100%_"));
+/// assert_eq!("100\\%\\_%", pattern);
+/// let condition = WhereCondition::like("label", &pattern);
+/// ```
+pub fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
         }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+/// A parameter owned by an [OwnedWhereCondition] or an
+/// [OwnedSqlQuery](crate::OwnedSqlQuery).
+pub type OwnedParameter = Box<dyn ToSqlAny + Send>;
+
+/// A macro to create a vector of owned parameters. The values are moved in the
+/// vector.
+#[macro_export]
+macro_rules! owned_params {
+    ($( $x:expr ),*) => {
+        {
+            let params: Vec<$crate::OwnedParameter> = vec![$(Box::new($x)),*];
+            params
+        }
+    };
+}
+
+/// A where condition that owns its parameters.
+/// Unlike [WhereCondition], it has no lifetime so it can be returned by a
+/// function, stored or sent to another task. It is borrowed as a
+/// [WhereCondition] to be used in the query books.
+///
+/// A borrowed condition is turned into an owned condition with
+/// [WhereCondition::into_owned].
+///
+/// # Examples
+/// ```rust
+/// use agrum::{OwnedWhereCondition, WhereCondition, owned_params};
+///
+/// fn active_in(city: String) -> OwnedWhereCondition {
+///     OwnedWhereCondition::new("city = $?", owned_params![city])
+///         .and_where(OwnedWhereCondition::new("is_active", Vec::new()))
+/// }
+///
+/// let condition = active_in("Paris".to_string());
+/// let borrowed = WhereCondition::from(&condition);
+/// assert_eq!("city = $? and is_active", borrowed.to_string());
+/// ```
+#[derive(Debug, Default)]
+pub struct OwnedWhereCondition {
+    condition: BooleanCondition,
+    parameters: Vec<OwnedParameter>,
+}
 
-        self
+impl Display for OwnedWhereCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.condition.expand())
+    }
+}
+
+impl OwnedWhereCondition {
+    /// Create a new condition with a SQL expression and the parameters.
+    pub fn new(expression: &str, parameters: Vec<OwnedParameter>) -> Self {
+        Self {
+            condition: BooleanCondition::Expression(expression.to_string()),
+            parameters,
+        }
+    }
+
+    /// Expand the condition to a SQL expression and the parameters (consuming the instance).
+    pub fn expand(self) -> (String, Vec<OwnedParameter>) {
+        (self.condition.expand(), self.parameters)
+    }
+
+    /// Create a new condition with a `IN` SQL expression and the parameters.
+    /// It creates as many `$?` placeholders as the number of parameters.
+    pub fn where_in(field: &str, parameters: Vec<OwnedParameter>) -> Self {
+        let params: Vec<&str> = repeat_n("$?", parameters.len()).collect();
+        let expression = format!("{} in ({})", field, params.join(", "));
+
+        Self {
+            condition: BooleanCondition::Expression(expression),
+            parameters,
+        }
+    }
+
+    /// Create a new condition with a `NOT IN` SQL expression and the
+    /// parameters.
+    pub fn where_not_in(field: &str, parameters: Vec<OwnedParameter>) -> Self {
+        Self::where_in(field, parameters).not()
+    }
+
+    /// Compose the condition with a `AND` boolean logic operator.
+    pub fn and_where(self, condition: OwnedWhereCondition) -> Self {
+        let (condition, parameters) = compose(
+            (self.condition, self.parameters),
+            (condition.condition, condition.parameters),
+            BooleanCondition::And,
+        );
+
+        Self {
+            condition,
+            parameters,
+        }
+    }
+
+    /// Compose the condition with a `OR` boolean logic operator.
+    pub fn or_where(self, condition: OwnedWhereCondition) -> Self {
+        let (condition, parameters) = compose(
+            (self.condition, self.parameters),
+            (condition.condition, condition.parameters),
+            BooleanCondition::Or,
+        );
+
+        Self {
+            condition,
+            parameters,
+        }
+    }
+}
+
+impl Not for OwnedWhereCondition {
+    type Output = Self;
+
+    /// Negate the condition, it is enclosed in parentheses so the negation
+    /// applies to the whole condition.
+    fn not(self) -> Self {
+        Self {
+            condition: BooleanCondition::Not(Box::new(self.condition)),
+            parameters: self.parameters,
+        }
     }
 }
 
@@ -397,4 +699,125 @@ mod tests {
 
         assert_eq!("a = $?", &sql);
     }
+
+    #[test]
+    fn owned_condition() {
+        let expression = OwnedWhereCondition::new("A > $?", owned_params![0_i32])
+            .or_where(OwnedWhereCondition::default())
+            .and_where(OwnedWhereCondition::where_in(
+                "B",
+                owned_params!["one".to_string(), "two".to_string()],
+            ));
+        let (sql, params) = WhereCondition::from(&expression).expand();
+
+        assert_eq!("A > $? and B in ($?, $?)", &sql);
+        assert_eq!(3, params.len());
+        let parameter: &String = (params[2] as &dyn std::any::Any).downcast_ref().unwrap();
+        assert_eq!(parameter, "two");
+    }
+
+    #[test]
+    fn condition_into_owned() {
+        let (a, b) = (1_i32, "two".to_string());
+        let owned = WhereCondition::new("a = $?", params![a])
+            .and_where(WhereCondition::new("b = $?", params![b]))
+            .into_owned();
+        let (sql, params) = WhereCondition::from(&owned).expand();
+
+        assert_eq!("a = $? and b = $?", &sql);
+        let parameter: &String = (params[1] as &dyn std::any::Any).downcast_ref().unwrap();
+        assert_eq!(parameter, "two");
+    }
+
+    #[test]
+    fn owned_condition_is_static_and_send() {
+        fn assert_static_send<T: Send + 'static>(_value: T) {}
+
+        assert_static_send(OwnedWhereCondition::new("A = $?", owned_params![1_i64]));
+    }
+
+    #[test]
+    fn expression_not() {
+        let expression = WhereCondition::new("A", Vec::new())
+            .or_where(WhereCondition::new("B", Vec::new()))
+            .not()
+            .and_where(WhereCondition::new("C", Vec::new()).not());
+        let (sql, params) = expression.expand();
+
+        assert_eq!("not (A or B) and not (C)", &sql);
+        assert!(params.is_empty());
+    }
+
+    #[test]
+    fn expression_not_in_or() {
+        let expression = WhereCondition::where_not_in("A", params![0_i32, 1_i32])
+            .or_where(WhereCondition::is_null("A"));
+        let (sql, params) = expression.expand();
+
+        assert_eq!("not (A in ($?, $?)) or A is null", &sql);
+        assert_eq!(2, params.len());
+    }
+
+    #[test]
+    fn expression_combinators() {
+        let values = vec![1_i32, 2_i32];
+        let expression = WhereCondition::between("A", &1_i32, &10_i32)
+            .and_where(WhereCondition::any("B", &values))
+            .and_where(WhereCondition::is_not_null("C"))
+            .and_where(
+                WhereCondition::like("D", &"x%").or_where(WhereCondition::ilike("E", &"y%")),
+            );
+        let (sql, params) = expression.expand();
+
+        assert_eq!(
+            "A between $? and $? and B = any($?) and C is not null and (D like $? or E ilike $?)",
+            &sql
+        );
+        assert_eq!(5, params.len());
+        let parameter: &Vec<i32> = (params[2] as &dyn std::any::Any).downcast_ref().unwrap();
+        assert_eq!(parameter, &values);
+    }
+
+    #[test]
+    fn expression_exists() {
+        let mut query = SqlCommand::new("select 1 from {:source:} where b = $:b and c = $?");
+        query
+            .set_variable("source", "some_table")
+            .set_named_parameter("b", &1_i32)
+            .add_parameter(&2_i32);
+        let expression = WhereCondition::new("A = $?", params![0_i32])
+            .and_where(WhereCondition::exists(query).not());
+        let (sql, params) = expression.expand();
+
+        assert_eq!(
+            "A = $? and not (exists (select 1 from some_table where b = $? and c = $?))",
+            &sql
+        );
+        let params: Vec<i32> = params
+            .into_iter()
+            .map(|p| *(p as &dyn std::any::Any).downcast_ref::<i32>().unwrap())
+            .collect();
+        assert_eq!(vec![0, 1, 2], params);
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!("a\\%b\\_c\\\\d", escape_like("a%b_c\\d"));
+        assert_eq!("plain", escape_like("plain"));
+    }
+
+    #[test]
+    fn expression_in_subquery() {
+        let mut query = SqlCommand::new("select id from some_table where b = $?");
+        query.add_parameter(&1_i32);
+        let expression = WhereCondition::new("A = $?", params![0_i32])
+            .or_where(WhereCondition::in_subquery("id", query));
+        let (sql, params) = expression.expand();
+
+        assert_eq!(
+            "A = $? or id in (select id from some_table where b = $?)",
+            &sql
+        );
+        assert_eq!(2, params.len());
+    }
 }
@@ -1,24 +1,33 @@
 use std::{
     marker::PhantomData,
     pin::Pin,
+    sync::Arc,
     task::{Context, Poll},
 };
 
-use crate::{Result, SqlEntity, SqlQuery};
+use crate::{ConstraintErrorMap, Error, Result, SqlCommand, SqlEntity, SqlQuery};
 use futures_core::Stream;
-use tokio_postgres::{RowStream, Transaction as TokioTransaction, types::ToSql};
+use futures_util::{StreamExt, pin_mut};
+use tokio_postgres::{
+    RowStream, Transaction as TokioTransaction, error::Error as PgError, types::ToSql,
+};
 
 /// A stream of entities.
 pub struct EntityStream<T: SqlEntity> {
     stream: Pin<Box<RowStream>>,
+    constraint_error_map: Option<Arc<ConstraintErrorMap>>,
     _phantom: PhantomData<T>,
 }
 
 impl<T: SqlEntity> EntityStream<T> {
     /// Create a new stream of entities.
-    pub(crate) fn new(stream: RowStream) -> Self {
+    pub(crate) fn new(
+        stream: RowStream,
+        constraint_error_map: Option<Arc<ConstraintErrorMap>>,
+    ) -> Self {
         Self {
             stream: Box::pin(stream),
+            constraint_error_map,
             _phantom: PhantomData,
         }
     }
@@ -28,15 +37,16 @@ impl<T: SqlEntity> Stream for EntityStream<T> {
     type Item = Result<T>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        // Safe: we only project to the `stream` field and call as_mut() on Pin<Box<RowStream>>;
+        // Safe: we only project to the fields and call as_mut() on Pin<Box<RowStream>>;
         // we do not move or unpin any part of Self.
-        let stream = unsafe { self.get_unchecked_mut().stream.as_mut() };
+        let this = unsafe { self.get_unchecked_mut() };
+        let constraint_error_map = this.constraint_error_map.as_deref();
 
-        match stream.poll_next(cx) {
+        match this.stream.as_mut().poll_next(cx) {
             Poll::Ready(Some(result)) => {
                 let item: Result<T> = result
-                    .map_err(anyhow::Error::from)
-                    .and_then(|row| T::hydrate(&row).map_err(anyhow::Error::from));
+                    .map_err(|e| map_error(constraint_error_map, e))
+                    .and_then(|row| T::hydrate(&row).map_err(Error::from));
                 Poll::Ready(Some(item))
             }
             Poll::Ready(None) => Poll::Ready(None),
@@ -50,7 +60,7 @@ impl<T: SqlEntity> Stream for EntityStream<T> {
 /// There is a bit of cabling in the query method to pass the parameters and
 /// instantiate the stream.
 pub struct Transaction<'a> {
-    transaction: TokioTransaction<'a>,
+    pub(crate) transaction: TokioTransaction<'a>,
 }
 
 impl<'a> Transaction<'a> {
@@ -71,11 +81,180 @@ impl<'a> Transaction<'a> {
         Ok(())
     }
 
-    /// Query the database with a query and return a stream of entities.
-    pub async fn query<E: SqlEntity>(&self, query: SqlQuery<'a, E>) -> Result<EntityStream<E>> {
+    /// Create a savepoint with the given name and return it as a nested
+    /// transaction. Committing the nested transaction releases the savepoint,
+    /// rolling it back discards the changes made since the savepoint while the
+    /// outer transaction goes on. Dropping it rolls it back.
+    pub async fn savepoint(&mut self, name: &str) -> Result<Transaction<'_>> {
+        let transaction = self.transaction.savepoint(name).await?;
+
+        Ok(Transaction { transaction })
+    }
+
+    /// Release the savepoint of a nested transaction, this is the same as
+    /// [Transaction::commit] but reads better for savepoints.
+    pub async fn release(self) -> Result<()> {
+        self.commit().await
+    }
+
+    /// Run the given closure in a nested transaction. The savepoint is
+    /// released if the closure succeeds and rolled back if it fails so the
+    /// outer transaction can go on whatever the outcome.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # use std::collections::HashMap;
+    /// # use agrum::{Error, InsertQueryBook, QueryBook, SqlEntity, Structured, ToSqlAny, Transaction};
+    /// # use futures_util::StreamExt;
+    /// # #[derive(SqlEntity, Structured)]
+    /// # struct Contact {
+    /// #     contact_id: uuid::Uuid,
+    /// #     name: String,
+    /// # }
+    /// # struct ContactQueryBook;
+    /// # impl QueryBook<Contact> for ContactQueryBook {
+    /// #     fn get_sql_source(&self) -> &'static str {
+    /// #         "pommr.contact"
+    /// #     }
+    /// # }
+    /// # impl InsertQueryBook<Contact> for ContactQueryBook {}
+    /// # async fn example(
+    /// #     transaction: &mut Transaction<'_>,
+    /// #     book: &ContactQueryBook,
+    /// #     values: HashMap<&str, &dyn ToSqlAny>,
+    /// # ) -> agrum::Result<()> {
+    /// let result = transaction
+    ///     .nested(async |transaction| {
+    ///         transaction.query(book.insert(values)).await?.next().await.unwrap()
+    ///     })
+    ///     .await;
+    ///
+    /// if let Err(Error::ConstraintViolation { .. }) = result {
+    ///     // the outer transaction is still usable
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn nested<R, E, F>(&mut self, f: F) -> std::result::Result<R, E>
+    where
+        F: AsyncFnOnce(&Transaction<'_>) -> std::result::Result<R, E>,
+        E: From<Error>,
+    {
+        let transaction = Transaction {
+            transaction: self.transaction.transaction().await.map_err(Error::from)?,
+        };
+
+        match f(&transaction).await {
+            Ok(result) => {
+                transaction.release().await?;
+                Ok(result)
+            }
+            Err(error) => {
+                transaction.rollback().await?;
+                Err(error)
+            }
+        }
+    }
+
+    /// Query the database with a query and return a stream of entities. The
+    /// query is either a [SqlQuery] or a reference to an
+    /// [OwnedSqlQuery](crate::OwnedSqlQuery).
+    /// If the query holds a [ConstraintErrorMap], the database errors are
+    /// mapped to the according domain errors.
+    pub async fn query<'q, E: SqlEntity>(
+        &self,
+        query: impl Into<SqlQuery<'q, E>>,
+    ) -> Result<EntityStream<E>> {
+        let query = query.into();
+        let constraint_error_map = query.get_constraint_error_map().cloned();
         let (statement, parameters) = query.expand();
-        let parameters: Vec<&dyn ToSql> = parameters.into_iter().map(|p| p as &dyn ToSql).collect();
-        let stream = self.transaction.query_raw(&statement, parameters).await?;
-        Ok(EntityStream::new(stream))
+        let parameters: Vec<&(dyn ToSql + Sync)> = parameters
+            .into_iter()
+            .map(|p| p as &(dyn ToSql + Sync))
+            .collect();
+        let stream = self
+            .transaction
+            .query_raw(&statement, parameters)
+            .await
+            .map_err(|e| map_error(constraint_error_map.as_deref(), e))?;
+        Ok(EntityStream::new(stream, constraint_error_map))
+    }
+
+    /// Query the database and return the only entity returned. An
+    /// [Error::NoRows] is returned if the query returns no rows and an
+    /// [Error::TooManyRows] if it returns more than one row.
+    pub async fn query_one<'q, E: SqlEntity>(
+        &self,
+        query: impl Into<SqlQuery<'q, E>>,
+    ) -> Result<E> {
+        self.query_opt(query).await?.ok_or(Error::NoRows)
+    }
+
+    /// Query the database and return the entity returned if any. An
+    /// [Error::TooManyRows] is returned if the query returns more than one row.
+    pub async fn query_opt<'q, E: SqlEntity>(
+        &self,
+        query: impl Into<SqlQuery<'q, E>>,
+    ) -> Result<Option<E>> {
+        let stream = self.query(query).await?;
+        pin_mut!(stream);
+        let Some(entity) = stream.next().await.transpose()? else {
+            return Ok(None);
+        };
+
+        match stream.next().await {
+            Some(Ok(_)) => Err(Error::TooManyRows),
+            Some(Err(error)) => Err(error),
+            None => Ok(Some(entity)),
+        }
+    }
+
+    /// Query the database and collect all the returned entities.
+    pub async fn query_all<'q, E: SqlEntity>(
+        &self,
+        query: impl Into<SqlQuery<'q, E>>,
+    ) -> Result<Vec<E>> {
+        let stream = self.query(query).await?;
+        pin_mut!(stream);
+        let mut entities = Vec::new();
+
+        while let Some(entity) = stream.next().await {
+            entities.push(entity?);
+        }
+
+        Ok(entities)
+    }
+
+    /// Execute the given command and return the number of affected rows. The
+    /// rows returned by the statement, if any, are ignored so queries can be
+    /// given as well.
+    /// If the command holds a [ConstraintErrorMap], the database errors are
+    /// mapped to the according domain errors.
+    pub async fn execute<'q>(&self, command: impl Into<SqlCommand<'q>>) -> Result<u64> {
+        let command = command.into();
+        let constraint_error_map = command.get_constraint_error_map().cloned();
+        let (statement, parameters) = command.expand();
+        let parameters: Vec<&(dyn ToSql + Sync)> = parameters
+            .into_iter()
+            .map(|p| p as &(dyn ToSql + Sync))
+            .collect();
+
+        self.transaction
+            .execute_raw(&statement, parameters)
+            .await
+            .map_err(|e| map_error(constraint_error_map.as_deref(), e))
+    }
+}
+
+/// Convert a driver error, mapping it to a domain error when a map is given.
+pub(crate) fn map_error(
+    constraint_error_map: Option<&ConstraintErrorMap>,
+    error: PgError,
+) -> Error {
+    let error = Error::from(error);
+
+    match constraint_error_map {
+        Some(map) => map.map_error(error),
+        None => error,
     }
 }
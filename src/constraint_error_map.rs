@@ -0,0 +1,193 @@
+use std::{collections::HashMap, fmt::Debug, sync::Arc};
+
+use tokio_postgres::error::{DbError, SqlState};
+
+use crate::{DomainError, Error};
+
+type DomainErrorFactory = Arc<dyn Fn(&DbError) -> DomainError + Send + Sync>;
+
+/// A registry that turns database errors into domain errors.
+/// Errors are mapped by constraint name first, then by SQLSTATE code. It is
+/// attached to a [QueryBook](crate::QueryBook) so the queries it builds return
+/// the mapped error as an [Error::Domain] instead of the raw driver error.
+///
+/// # Examples
+/// ```rust
+/// use std::fmt::Display;
+/// use agrum::ConstraintErrorMap;
+///
+/// #[derive(Debug)]
+/// enum ContactError {
+///     IsDefaultContact,
+///     AlreadyExists,
+/// }
+///
+/// impl Display for ContactError {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         write!(f, "{self:?}")
+///     }
+/// }
+///
+/// impl std::error::Error for ContactError {}
+///
+/// let map = ConstraintErrorMap::new()
+///     .on_constraint("address_have_one_default_contact", |_| ContactError::IsDefaultContact)
+///     .on_unique_violation(|_| ContactError::AlreadyExists);
+/// ```
+#[derive(Clone, Default)]
+pub struct ConstraintErrorMap {
+    constraints: HashMap<String, DomainErrorFactory>,
+    codes: HashMap<String, DomainErrorFactory>,
+}
+
+impl ConstraintErrorMap {
+    /// Create an empty map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map the violation of the given constraint to a domain error.
+    pub fn on_constraint<E, F>(mut self, constraint: &str, factory: F) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+        F: Fn(&DbError) -> E + Send + Sync + 'static,
+    {
+        self.constraints.insert(
+            constraint.to_string(),
+            Arc::new(move |error| Box::new(factory(error))),
+        );
+
+        self
+    }
+
+    /// Map the errors with the given SQLSTATE code to a domain error.
+    pub fn on_code<E, F>(mut self, code: SqlState, factory: F) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+        F: Fn(&DbError) -> E + Send + Sync + 'static,
+    {
+        self.codes.insert(
+            code.code().to_string(),
+            Arc::new(move |error| Box::new(factory(error))),
+        );
+
+        self
+    }
+
+    /// Map unique constraint violations to a domain error.
+    pub fn on_unique_violation<E, F>(self, factory: F) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+        F: Fn(&DbError) -> E + Send + Sync + 'static,
+    {
+        self.on_code(SqlState::UNIQUE_VIOLATION, factory)
+    }
+
+    /// Map foreign key constraint violations to a domain error.
+    pub fn on_foreign_key_violation<E, F>(self, factory: F) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+        F: Fn(&DbError) -> E + Send + Sync + 'static,
+    {
+        self.on_code(SqlState::FOREIGN_KEY_VIOLATION, factory)
+    }
+
+    /// Map check constraint violations to a domain error.
+    pub fn on_check_violation<E, F>(self, factory: F) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+        F: Fn(&DbError) -> E + Send + Sync + 'static,
+    {
+        self.on_code(SqlState::CHECK_VIOLATION, factory)
+    }
+
+    /// Map exclusion constraint violations to a domain error.
+    pub fn on_exclusion_violation<E, F>(self, factory: F) -> Self
+    where
+        E: std::error::Error + Send + Sync + 'static,
+        F: Fn(&DbError) -> E + Send + Sync + 'static,
+    {
+        self.on_code(SqlState::EXCLUSION_VIOLATION, factory)
+    }
+
+    /// Turn the given error into a domain error if it matches a registered
+    /// constraint name or SQLSTATE code. Other errors are returned untouched.
+    pub fn map_error(&self, error: Error) -> Error {
+        let Some(db_error) = error.db_error() else {
+            return error;
+        };
+        let factory = db_error
+            .constraint()
+            .and_then(|constraint| self.constraints.get(constraint))
+            .or_else(|| self.codes.get(db_error.code().code()));
+
+        match factory {
+            Some(factory) => Error::Domain(factory(db_error)),
+            None => error,
+        }
+    }
+
+    /// Return true if no error is mapped.
+    pub fn is_empty(&self) -> bool {
+        self.constraints.is_empty() && self.codes.is_empty()
+    }
+}
+
+impl Debug for ConstraintErrorMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConstraintErrorMap")
+            .field("constraints", &self.constraints.keys().collect::<Vec<_>>())
+            .field("codes", &self.codes.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fmt::Display;
+
+    use crate::HydrationError;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestError;
+
+    impl Display for TestError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "test error")
+        }
+    }
+
+    impl std::error::Error for TestError {}
+
+    #[test]
+    fn empty_map() {
+        let map = ConstraintErrorMap::new();
+
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn register_errors() {
+        let map = ConstraintErrorMap::new()
+            .on_constraint("some_constraint", |_| TestError)
+            .on_unique_violation(|_| TestError);
+
+        assert!(!map.is_empty());
+        assert_eq!(
+            r#"ConstraintErrorMap { constraints: ["some_constraint"], codes: ["23505"] }"#,
+            format!("{map:?}")
+        );
+    }
+
+    #[test]
+    fn map_error_without_database_error() {
+        let map = ConstraintErrorMap::new().on_foreign_key_violation(|_| TestError);
+        let error = map.map_error(Error::Hydration(HydrationError::InvalidData(
+            "whatever".to_string(),
+        )));
+
+        assert!(matches!(error, Error::Hydration(_)));
+    }
+}
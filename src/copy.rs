@@ -0,0 +1,467 @@
+use std::{
+    marker::PhantomData,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use futures_core::Stream;
+use futures_util::{StreamExt, pin_mut};
+use tokio_postgres::{
+    CopyOutStream, Statement,
+    binary_copy::{BinaryCopyInWriter, BinaryCopyOutRow, BinaryCopyOutStream},
+    types::{ToSql, Type},
+};
+
+use crate::{
+    ConstraintErrorMap, Error, HydrationError, QueryBook, Result, SqlCommand, SqlEntity, SqlQuery,
+    Structure, ToSqlAny, Transaction,
+    connection::map_error,
+    template::{Segment, value_segments},
+};
+
+/// A trait for the rows sent to the database with a `COPY … FROM STDIN`
+/// statement. The values must be given in the order of the fields of the
+/// structure they are copied to.
+/// It can be derived for entities, the values being the fields in declaration
+/// order, which is the order of the derived structure.
+pub trait ToCopyRow {
+    /// Return the values of the row in the structure order.
+    fn to_copy_row(&self) -> Vec<&(dyn ToSql + Sync)>;
+}
+
+impl ToCopyRow for Vec<&dyn ToSqlAny> {
+    fn to_copy_row(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.iter()
+            .map(|value| *value as &(dyn ToSql + Sync))
+            .collect()
+    }
+}
+
+/// A trait for the entities read from a binary `COPY … TO STDOUT` statement.
+/// The values of the row are fetched by position so they must be in the order
+/// of the projection.
+/// It can be derived for entities, the fields being hydrated in declaration
+/// order, which is the order of the derived projection.
+pub trait FromCopyRow: Sized {
+    /// Hydrate the entity from the copied row.
+    fn from_copy_row(row: &BinaryCopyOutRow) -> std::result::Result<Self, HydrationError>;
+}
+
+/// Format of the data sent by a `COPY … TO STDOUT` statement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyFormat {
+    /// Tab separated text format.
+    Text,
+
+    /// Comma separated values, with or without a header line.
+    Csv {
+        /// Whether the first line holds the field names.
+        header: bool,
+    },
+
+    /// PostgreSQL binary format.
+    Binary,
+}
+
+impl CopyFormat {
+    /// Return the options of the `COPY` statement for this format.
+    fn get_options(&self) -> &'static str {
+        match self {
+            Self::Text => "format text",
+            Self::Csv { header: false } => "format csv",
+            Self::Csv { header: true } => "format csv, header true",
+            Self::Binary => "format binary",
+        }
+    }
+}
+
+/// A stream of entities decoded from a binary `COPY … TO STDOUT` statement.
+pub struct CopyEntityStream<T: FromCopyRow> {
+    stream: Pin<Box<BinaryCopyOutStream>>,
+    constraint_error_map: Option<Arc<ConstraintErrorMap>>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: FromCopyRow> Stream for CopyEntityStream<T> {
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // Safe: we only project to the fields and call as_mut() on Pin<Box<BinaryCopyOutStream>>;
+        // we do not move or unpin any part of Self.
+        let this = unsafe { self.get_unchecked_mut() };
+        let constraint_error_map = this.constraint_error_map.as_deref();
+
+        match this.stream.as_mut().poll_next(cx) {
+            Poll::Ready(Some(result)) => {
+                let item: Result<T> = result
+                    .map_err(|e| map_error(constraint_error_map, e))
+                    .and_then(|row| T::from_copy_row(&row).map_err(Error::from));
+                Poll::Ready(Some(item))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// A stream of the raw data sent by a `COPY … TO STDOUT` statement.
+pub struct CopyByteStream {
+    stream: Pin<Box<CopyOutStream>>,
+    constraint_error_map: Option<Arc<ConstraintErrorMap>>,
+}
+
+impl Stream for CopyByteStream {
+    type Item = Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let constraint_error_map = this.constraint_error_map.as_deref();
+
+        this.stream
+            .as_mut()
+            .poll_next(cx)
+            .map(|item| item.map(|result| result.map_err(|e| map_error(constraint_error_map, e))))
+    }
+}
+
+impl Transaction<'_> {
+    /// Load the given rows in the SQL source of the query book using a binary
+    /// `COPY source (fields) FROM STDIN` statement, the fields being the fields
+    /// of the structure of `T`. This is the fastest way to load large amounts of
+    /// data. It returns the number of copied rows.
+    /// The SQL types of the fields are fetched from the server prior to the
+    /// copy. The database errors are turned into domain errors with the
+    /// [ConstraintErrorMap] of the book, like the errors of the queries.
+    ///
+    /// An [Error::Copy] is returned if a row does not have as many values as
+    /// the structure has fields, the copy is then aborted.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # use agrum::{QueryBook, SqlEntity, Structured, ToCopyRow, Transaction};
+    /// # #[derive(SqlEntity, Structured, ToCopyRow)]
+    /// # struct Contact {
+    /// #     contact_id: uuid::Uuid,
+    /// #     name: String,
+    /// # }
+    /// # struct ContactQueryBook;
+    /// # impl QueryBook<Contact> for ContactQueryBook {
+    /// #     fn get_sql_source(&self) -> &'static str {
+    /// #         "pommr.contact"
+    /// #     }
+    /// # }
+    /// # async fn example(transaction: &Transaction<'_>, contacts: Vec<Contact>) -> agrum::Result<()> {
+    /// let copied = transaction
+    ///     .copy_in(&ContactQueryBook, futures_util::stream::iter(contacts))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn copy_in<T: SqlEntity>(
+        &self,
+        book: &impl QueryBook<T>,
+        rows: impl Stream<Item = impl ToCopyRow>,
+    ) -> Result<u64> {
+        let source = book.get_sql_source();
+        let constraint_error_map = book.get_constraint_error_map();
+        let map = |e| map_error(constraint_error_map.as_deref(), e);
+        let structure = T::get_structure();
+        let types: Vec<Type> = self
+            .transaction
+            .prepare(&format!(
+                "select {} from {source}",
+                structure.get_names().join(", ")
+            ))
+            .await
+            .map_err(map)?
+            .columns()
+            .iter()
+            .map(|column| column.type_().clone())
+            .collect();
+        let sink = self
+            .transaction
+            .copy_in(&copy_in_statement(source, &structure))
+            .await
+            .map_err(map)?;
+        let writer = BinaryCopyInWriter::new(sink, &types);
+        pin_mut!(writer);
+        pin_mut!(rows);
+
+        while let Some(row) = rows.next().await {
+            let values = row.to_copy_row();
+
+            if values.len() != types.len() {
+                return Err(Error::Copy(format!(
+                    "{} values in a row of {} fields",
+                    values.len(),
+                    types.len()
+                )));
+            }
+
+            writer.as_mut().write(&values).await.map_err(map)?;
+        }
+
+        writer.finish().await.map_err(map)
+    }
+
+    /// Run the query in a binary `COPY (query) TO STDOUT` statement and return
+    /// a stream of entities. This is faster than [Transaction::query] for large
+    /// result sets. The query is prepared prior to the copy to fetch the SQL
+    /// types of the projection, which costs one more round trip.
+    ///
+    /// `COPY` statements cannot take parameters, the parameters of the query
+    /// are sent to the server beforehand to be turned into SQL literals, see
+    /// [Transaction::copy_out_raw]. The types of the parameters are read from
+    /// the same prepared query so this costs two more round trips when the
+    /// query has parameters.
+    pub async fn copy_out<'q, E: SqlEntity + FromCopyRow>(
+        &self,
+        query: impl Into<SqlQuery<'q, E>>,
+    ) -> Result<CopyEntityStream<E>> {
+        let query = query.into();
+        let constraint_error_map = query.get_constraint_error_map().cloned();
+        let (query, statement) = self
+            .copy_out_query(query.into(), constraint_error_map.as_deref())
+            .await?;
+        let statement = match statement {
+            Some(statement) => statement,
+            None => self
+                .transaction
+                .prepare(&query)
+                .await
+                .map_err(|e| map_error(constraint_error_map.as_deref(), e))?,
+        };
+        let types: Vec<Type> = statement
+            .columns()
+            .iter()
+            .map(|column| column.type_().clone())
+            .collect();
+        let stream = self
+            .transaction
+            .copy_out(&copy_out_statement(&query, CopyFormat::Binary))
+            .await
+            .map_err(|e| map_error(constraint_error_map.as_deref(), e))?;
+
+        Ok(CopyEntityStream {
+            stream: Box::pin(BinaryCopyOutStream::new(stream, &types)),
+            constraint_error_map,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Run the query in a `COPY (query) TO STDOUT` statement and return the raw
+    /// data in the given format, typically to dump it in a CSV file.
+    ///
+    /// `COPY` statements cannot take parameters, so each parameter of the query
+    /// is replaced by a literal quoted by the server according to the type it
+    /// infers for the parameter, like `'a7b5…'::uuid`. This costs three more
+    /// round trips when the query has parameters: the query is prepared to
+    /// infer the types of the parameters, then a statement quoting them is
+    /// prepared and run. An [Error::Copy] is returned if the query does not
+    /// have as many parameters as placeholders.
+    pub async fn copy_out_raw<'q, E: SqlEntity>(
+        &self,
+        query: impl Into<SqlQuery<'q, E>>,
+        format: CopyFormat,
+    ) -> Result<CopyByteStream> {
+        let query = query.into();
+        let constraint_error_map = query.get_constraint_error_map().cloned();
+        let (query, _) = self
+            .copy_out_query(query.into(), constraint_error_map.as_deref())
+            .await?;
+        let stream = self
+            .transaction
+            .copy_out(&copy_out_statement(&query, format))
+            .await
+            .map_err(|e| map_error(constraint_error_map.as_deref(), e))?;
+
+        Ok(CopyByteStream {
+            stream: Box::pin(stream),
+            constraint_error_map,
+        })
+    }
+
+    /// Expand the query to be copied, its parameters being replaced by the SQL
+    /// literals returned by the server since `COPY` statements cannot be
+    /// prepared. When the query has parameters, the statement prepared to
+    /// infer their types is returned along with the query.
+    async fn copy_out_query(
+        &self,
+        query: SqlCommand<'_>,
+        constraint_error_map: Option<&ConstraintErrorMap>,
+    ) -> Result<(String, Option<Statement>)> {
+        let (query, parameters) = query.expand_positional();
+
+        if parameters.is_empty() {
+            return Ok((query, None));
+        }
+
+        let prepared = self
+            .transaction
+            .prepare(&bind_parameters(&query, |index| format!("${}", index + 1)))
+            .await
+            .map_err(|e| map_error(constraint_error_map, e))?;
+        let types = prepared.params().to_vec();
+
+        if types.len() != parameters.len() {
+            return Err(Error::Copy(format!(
+                "{} parameter placeholders for {} parameters",
+                types.len(),
+                parameters.len()
+            )));
+        }
+
+        let literals = (1..=types.len())
+            .map(|index| format!("format('%L::%s', ${index}, pg_typeof(${index}))"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let statement = self
+            .transaction
+            .prepare_typed(&format!("select {literals}"), &types)
+            .await
+            .map_err(|e| map_error(constraint_error_map, e))?;
+        let parameters: Vec<&(dyn ToSql + Sync)> = parameters
+            .into_iter()
+            .map(|p| p as &(dyn ToSql + Sync))
+            .collect();
+        let row = self
+            .transaction
+            .query_one(&statement, &parameters)
+            .await
+            .map_err(|e| map_error(constraint_error_map, e))?;
+        let literals = (0..row.len())
+            .map(|index| row.try_get(index))
+            .collect::<std::result::Result<Vec<String>, _>>()?;
+
+        Ok((
+            bind_parameters(&query, |index| literals[index].clone()),
+            Some(prepared),
+        ))
+    }
+}
+
+/// Return the `COPY … FROM STDIN` statement for the given source and structure.
+fn copy_in_statement(source: &str, structure: &Structure) -> String {
+    format!(
+        "copy {source} ({}) from stdin with (format binary)",
+        structure.get_names().join(", ")
+    )
+}
+
+/// Replace the `$?` placeholders of the query by the values returned for
+/// their index.
+fn bind_parameters(query: &str, mut bind: impl FnMut(usize) -> String) -> String {
+    let mut bound = String::with_capacity(query.len());
+    let mut index = 0;
+
+    for segment in value_segments(query) {
+        match segment {
+            Segment::Literal(text) | Segment::Variable(text) => bound.push_str(text),
+            Segment::Parameter => {
+                bound.push_str(&bind(index));
+                index += 1;
+            }
+            Segment::NamedParameter(name) => {
+                bound.push_str("$:");
+                bound.push_str(name);
+            }
+        }
+    }
+
+    bound
+}
+
+/// Return the `COPY … TO STDOUT` statement for the given query and format.
+fn copy_out_statement(query: &str, format: CopyFormat) -> String {
+    format!(
+        "copy ({}) to stdout with ({})",
+        query.trim().trim_end_matches(';'),
+        format.get_options()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use crate::{Projection, Structured, params};
+
+    use super::*;
+
+    #[test]
+    fn test_copy_in_statement() {
+        let structure = Structure::new(&[("id", "integer"), ("name", "text")]);
+
+        assert_eq!(
+            "copy some_schema.entity_table (id, name) from stdin with (format binary)",
+            copy_in_statement("some_schema.entity_table", &structure)
+        );
+    }
+
+    #[test]
+    fn test_values_to_copy_row() {
+        let row = params![1_i32, "name"];
+        let values = row.to_copy_row();
+
+        assert_eq!(values.len(), 2);
+        let value: &i32 = (row[0] as &dyn Any).downcast_ref().unwrap();
+        assert_eq!(value, &1_i32);
+    }
+
+    struct TestEntity;
+
+    impl SqlEntity for TestEntity {
+        fn get_projection() -> Projection<Self> {
+            Projection::default()
+        }
+
+        fn hydrate(_row: &tokio_postgres::Row) -> std::result::Result<Self, HydrationError> {
+            Ok(Self)
+        }
+    }
+
+    impl Structured for TestEntity {
+        fn get_structure() -> Structure {
+            Structure::new(&[("id", "integer")])
+        }
+    }
+
+    #[test]
+    fn test_copy_out_statement() {
+        assert_eq!(
+            "copy (select id from some_table) to stdout with (format binary)",
+            copy_out_statement("select id from some_table;\n", CopyFormat::Binary)
+        );
+        assert_eq!(
+            "copy (select id from some_table) to stdout with (format csv, header true)",
+            copy_out_statement(
+                "select id from some_table",
+                CopyFormat::Csv { header: true }
+            )
+        );
+        assert_eq!(
+            "copy (select id from some_table) to stdout with (format text)",
+            copy_out_statement("select id from some_table", CopyFormat::Text)
+        );
+    }
+
+    #[test]
+    fn test_bind_parameters() {
+        let mut query = SqlQuery::<TestEntity>::new(
+            "select {:projection:} from some_table where id = $? and name = $:name",
+        );
+        query
+            .set_parameters(params![1_i32])
+            .set_named_parameter("name", &"it's");
+        let (query, parameters) = SqlCommand::from(query).expand_positional();
+
+        assert_eq!(2, parameters.len());
+        assert_eq!(
+            "select id as id from some_table where id = '1'::integer and name = 'it''s'::text",
+            bind_parameters(&query, |index| ["'1'::integer", "'it''s'::text"][index]
+                .to_string())
+        );
+    }
+}
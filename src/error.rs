@@ -0,0 +1,268 @@
+use std::fmt::Display;
+
+use bb8::RunError;
+use tokio_postgres::error::{DbError, Error as PgError, SqlState};
+
+use crate::{HydrationError, TemplateError};
+
+/// A user defined error returned in place of a database error, see
+/// [ConstraintErrorMap](crate::ConstraintErrorMap).
+pub type DomainError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Error raised by Agrum when talking to the database.
+/// Errors returned by the server are sorted by their SQLSTATE so the caller can
+/// match on what went wrong without downcasting.
+#[derive(Debug)]
+pub enum Error {
+    /// The connection to the database server failed or was closed.
+    Connection(PgError),
+
+    /// No connection could be taken from the pool in time.
+    PoolTimeout,
+
+    /// The SQL statement failed.
+    Sql {
+        /// SQLSTATE code returned by the server, `None` when the error is raised
+        /// by the client library (for instance when a parameter cannot be
+        /// serialized to the expected SQL type).
+        code: Option<SqlState>,
+        /// Underlying driver error.
+        error: PgError,
+    },
+
+    /// The SQL statement violated a database constraint.
+    ConstraintViolation {
+        /// Name of the violated constraint.
+        constraint: String,
+        /// SQLSTATE code returned by the server.
+        code: SqlState,
+        /// Underlying driver error.
+        error: PgError,
+    },
+
+    /// The query returned no rows when one was expected.
+    NoRows,
+
+    /// The query returned more rows than expected.
+    TooManyRows,
+
+    /// The entity could not be hydrated from the returned row.
+    Hydration(HydrationError),
+
+    /// The SQL template could not be expanded.
+    Template(TemplateError),
+
+    /// The query or the rows cannot be run in a `COPY` statement.
+    Copy(String),
+
+    /// A pagination cursor could not be decoded or does not match the keyset
+    /// of the query, see [Cursor](crate::Cursor).
+    InvalidCursor(String),
+
+    /// A database error mapped to a user defined error by a
+    /// [ConstraintErrorMap](crate::ConstraintErrorMap).
+    Domain(DomainError),
+
+    /// The transaction kept failing with serialization failures or deadlocks,
+    /// see [run_in_transaction](crate::run_in_transaction).
+    RetriesExhausted {
+        /// Number of times the transaction has been run.
+        attempts: u32,
+        /// Error of the last attempt.
+        last_error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Return the SQLSTATE code of the error if it has been raised by the server.
+    pub fn code(&self) -> Option<&SqlState> {
+        match self {
+            Self::Sql { code, .. } => code.as_ref(),
+            Self::ConstraintViolation { code, .. } => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Return the name of the violated constraint if any.
+    pub fn constraint(&self) -> Option<&str> {
+        match self {
+            Self::ConstraintViolation { constraint, .. } => Some(constraint),
+            _ => None,
+        }
+    }
+
+    /// Return true if the transaction failed because of a serialization
+    /// failure or a deadlock. Replaying the whole transaction may succeed.
+    pub fn is_retryable(&self) -> bool {
+        self.code().is_some_and(|code| {
+            code == &SqlState::T_R_SERIALIZATION_FAILURE || code == &SqlState::T_R_DEADLOCK_DETECTED
+        })
+    }
+
+    /// Return the error as sent by the server if any.
+    pub fn db_error(&self) -> Option<&DbError> {
+        match self {
+            Self::Connection(error)
+            | Self::Sql { error, .. }
+            | Self::ConstraintViolation { error, .. } => error.as_db_error(),
+            _ => None,
+        }
+    }
+}
+
+impl From<PgError> for Error {
+    fn from(error: PgError) -> Self {
+        if let Some(db_error) = error.as_db_error() {
+            let code = db_error.code().clone();
+
+            return match db_error.constraint() {
+                Some(constraint) if code.code().starts_with("23") => Self::ConstraintViolation {
+                    constraint: constraint.to_string(),
+                    code,
+                    error,
+                },
+                _ => Self::Sql {
+                    code: Some(code),
+                    error,
+                },
+            };
+        }
+
+        let is_io = std::error::Error::source(&error)
+            .is_some_and(|source| source.downcast_ref::<std::io::Error>().is_some());
+
+        if error.is_closed() || is_io {
+            Self::Connection(error)
+        } else {
+            Self::Sql { code: None, error }
+        }
+    }
+}
+
+impl From<RunError<PgError>> for Error {
+    fn from(error: RunError<PgError>) -> Self {
+        match error {
+            RunError::User(error) => Self::from(error),
+            RunError::TimedOut => Self::PoolTimeout,
+        }
+    }
+}
+
+impl From<HydrationError> for Error {
+    fn from(error: HydrationError) -> Self {
+        Self::Hydration(error)
+    }
+}
+
+impl From<TemplateError> for Error {
+    fn from(error: TemplateError) -> Self {
+        Self::Template(error)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Connection(error) => write!(f, "Connection error: «{error}»."),
+            Self::PoolTimeout => write!(f, "Timed out waiting for a pool connection."),
+            Self::Sql {
+                code: Some(code),
+                error,
+            } => write!(f, "SQL error [{}]: «{error}».", code.code()),
+            Self::Sql { code: None, error } => write!(f, "SQL error: «{error}»."),
+            Self::ConstraintViolation {
+                constraint,
+                code,
+                error,
+            } => write!(
+                f,
+                "Constraint '{constraint}' violated [{}]: «{error}».",
+                code.code()
+            ),
+            Self::NoRows => write!(f, "The query returned no rows."),
+            Self::TooManyRows => write!(f, "The query returned more than one row."),
+            Self::Hydration(error) => write!(f, "Hydration error: {error}"),
+            Self::Template(error) => write!(f, "Template error: «{error}»."),
+            Self::Copy(message) => write!(f, "Copy error: «{message}»."),
+            Self::InvalidCursor(message) => write!(f, "Invalid cursor: «{message}»."),
+            Self::Domain(error) => write!(f, "{error}"),
+            Self::RetriesExhausted {
+                attempts,
+                last_error,
+            } => write!(
+                f,
+                "Transaction failed after {attempts} attempts, last error: {last_error}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Connection(error)
+            | Self::Sql { error, .. }
+            | Self::ConstraintViolation { error, .. } => Some(error),
+            Self::Hydration(error) => Some(error),
+            Self::Template(error) => Some(error),
+            Self::PoolTimeout
+            | Self::NoRows
+            | Self::TooManyRows
+            | Self::Copy(_)
+            | Self::InvalidCursor(_) => None,
+            Self::Domain(error) => Some(error.as_ref()),
+            Self::RetriesExhausted { last_error, .. } => Some(last_error.as_ref()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_hydration_error() {
+        let error = Error::from(HydrationError::InvalidData("not a number".to_string()));
+
+        assert!(matches!(error, Error::Hydration(_)));
+        assert_eq!(None, error.code());
+        assert_eq!(None, error.constraint());
+        assert_eq!(
+            "Hydration error: Invalid data error: «not a number»",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn template_error() {
+        let error = Error::from(TemplateError::UnresolvedVariable("source".to_string()));
+
+        assert!(error.db_error().is_none());
+        assert_eq!(
+            "Template error: «variable 'source' has no value».",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn pool_timeout() {
+        let error = Error::from(RunError::TimedOut);
+
+        assert!(matches!(error, Error::PoolTimeout));
+        assert!(!error.is_retryable());
+    }
+
+    #[test]
+    fn retries_exhausted() {
+        let error = Error::RetriesExhausted {
+            attempts: 3,
+            last_error: Box::new(Error::Copy("whatever".to_string())),
+        };
+
+        assert_eq!(
+            "Transaction failed after 3 attempts, last error: Copy error: «whatever».",
+            error.to_string()
+        );
+        assert!(std::error::Error::source(&error).is_some());
+    }
+}
@@ -10,18 +10,57 @@
 //! ready**. If you are looking for a mature solution, have a look at
 //! [Elephantry](https://elephantry.github.io/)
 
+// Let the derive macros refer to `::agrum` from within the crate itself.
+extern crate self as agrum;
+
+mod command;
 mod condition;
 mod connection;
+mod constraint_error_map;
+mod copy;
+mod error;
+mod pagination;
+mod pool;
 mod projection;
 mod query;
 mod query_book;
+mod retry;
+mod scalar;
+mod select_options;
 mod structure;
+mod template;
+mod transaction_builder;
+mod updates;
+mod with_query;
 
+pub use command::*;
 pub use condition::*;
 pub use connection::*;
+pub use constraint_error_map::*;
+pub use copy::*;
+pub use error::*;
+pub use pagination::*;
+pub use pool::*;
 pub use projection::*;
 pub use query::*;
 pub use query_book::*;
+pub use retry::*;
+pub use scalar::*;
+pub use select_options::*;
 pub use structure::*;
+pub use template::*;
+pub use transaction_builder::*;
+pub use updates::*;
+pub use with_query::*;
+
+pub use agrum_derive::{FromCopyRow, SqlEntity, Structured, ToCopyRow};
+
+/// Items used by the code generated by the derive macros, not part of the
+/// public API.
+#[doc(hidden)]
+pub mod __private {
+    pub use tokio_postgres::{Row, binary_copy::BinaryCopyOutRow, types::ToSql};
+}
 
-type Result<T> = anyhow::Result<T>;
+/// Result type of the database operations.
+pub type Result<T> = std::result::Result<T, Error>;
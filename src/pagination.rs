@@ -0,0 +1,510 @@
+use std::fmt::Display;
+
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use serde_json::Value;
+use tokio_postgres::{Row, types::ToSql};
+
+use crate::{
+    Error, Projection, Result, SortOrder, SqlEntity, SqlQuery, Structured, ToSqlAny, Transaction,
+    WhereCondition, connection::map_error,
+};
+
+/// The fields the rows of a keyset pagination are sorted by, see
+/// [ReadQueryBook::paginate_after](crate::ReadQueryBook::paginate_after).
+/// The pages are delimited with a row value comparison like
+/// `(name, contact_id) > ($?, $?)` so the database can seek the next page in
+/// an index instead of skipping the rows of the previous pages. All the fields
+/// are sorted in the same order and, taken together, they must be unique and
+/// not null, the last fields usually being the primary key.
+///
+/// # Examples
+/// ```rust
+/// use agrum::{Keyset, SortOrder};
+///
+/// let keyset = Keyset::new(&["name", "contact_id"], SortOrder::Asc);
+///
+/// assert_eq!(&["name", "contact_id"], keyset.get_fields());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keyset {
+    fields: Vec<String>,
+    order: SortOrder,
+}
+
+impl Keyset {
+    /// Create a keyset from the given projection fields.
+    ///
+    /// It panics if no field is given.
+    pub fn new(fields: &[&str], order: SortOrder) -> Self {
+        if fields.is_empty() {
+            panic!("A keyset needs at least one field.");
+        }
+
+        Self {
+            fields: fields.iter().map(|field| field.to_string()).collect(),
+            order,
+        }
+    }
+
+    /// Create a keyset made of the primary key of the structure of `T`, in
+    /// ascending order.
+    ///
+    /// It panics if the structure declares no primary key.
+    pub fn primary_key<T: Structured>() -> Self {
+        let structure = T::get_structure();
+        let primary_key = structure.get_primary_key();
+
+        if primary_key.is_empty() {
+            panic!("The structure declares no primary key, a keyset must be given.");
+        }
+
+        Self::new(&primary_key, SortOrder::Asc)
+    }
+
+    /// Return the fields of the keyset in order.
+    pub fn get_fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    /// Return the order the rows are sorted in.
+    pub fn get_order(&self) -> SortOrder {
+        self.order
+    }
+
+    /// Return the clauses of a page query starting at the given cursor.
+    ///
+    /// It panics if a field of the keyset is not a field of the projection.
+    pub(crate) fn expand<'a, T: SqlEntity>(
+        &self,
+        projection: &Projection<T>,
+        cursor: Option<&'a Cursor>,
+    ) -> Result<KeysetClauses<'a>> {
+        let structure = projection.get_structure();
+        let keys: Vec<(&str, &str)> = self
+            .fields
+            .iter()
+            .map(|field| {
+                let Some(definition) = projection.get_definition(field) else {
+                    panic!(
+                        "Field {field} not found in projection. Available fields: '{}'.",
+                        projection.get_fields().join(", ")
+                    );
+                };
+                let sql_type = structure
+                    .get_fields()
+                    .iter()
+                    .map(|structure_field| structure_field.dump())
+                    .find_map(|(name, sql_type)| (name == field).then_some(sql_type))
+                    .expect("projection fields are structure fields");
+
+                (definition, sql_type)
+            })
+            .collect();
+        let backward = cursor.is_some_and(|cursor| cursor.direction == Direction::Before);
+        let order = match (self.order, backward) {
+            (SortOrder::Asc, false) | (SortOrder::Desc, true) => SortOrder::Asc,
+            (SortOrder::Desc, false) | (SortOrder::Asc, true) => SortOrder::Desc,
+        };
+        let columns = keys
+            .iter()
+            .enumerate()
+            .map(|(index, (definition, _))| {
+                format!("({definition})::text as {CURSOR_COLUMN}{index}")
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let order_by = keys
+            .iter()
+            .map(|(definition, _)| format!("{definition} {}", order.as_sql()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let condition = cursor
+            .map(|cursor| {
+                if cursor.values.len() != keys.len() {
+                    return Err(Error::InvalidCursor(format!(
+                        "{} values for a keyset of {} fields",
+                        cursor.values.len(),
+                        keys.len()
+                    )));
+                }
+
+                let fields: Vec<&str> = keys.iter().map(|(definition, _)| *definition).collect();
+                let values: Vec<String> = keys
+                    .iter()
+                    .map(|(_, sql_type)| format!("cast($?::text as {sql_type})"))
+                    .collect();
+                let operator = match order {
+                    SortOrder::Asc => ">",
+                    SortOrder::Desc => "<",
+                };
+
+                Ok(WhereCondition::new(
+                    &format!("({}) {operator} ({})", fields.join(", "), values.join(", ")),
+                    cursor
+                        .values
+                        .iter()
+                        .map(|value| value as &dyn ToSqlAny)
+                        .collect(),
+                ))
+            })
+            .transpose()?;
+
+        Ok(KeysetClauses {
+            columns,
+            condition,
+            order_by,
+        })
+    }
+}
+
+/// Prefix of the columns holding the keyset values of the rows.
+const CURSOR_COLUMN: &str = "agrum_cursor_";
+
+/// The SQL clauses a keyset adds to a page query.
+pub(crate) struct KeysetClauses<'a> {
+    /// The keyset values of the rows, as text.
+    pub(crate) columns: String,
+
+    /// The row value comparison with the cursor, if any.
+    pub(crate) condition: Option<WhereCondition<'a>>,
+
+    /// The fields of the `order by` clause.
+    pub(crate) order_by: String,
+}
+
+/// Side of the cursor the page is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    After,
+    Before,
+}
+
+/// An opaque position in a keyset pagination. It holds the keyset values of
+/// the row it starts from and whether the page is after or before that row.
+/// Cursors are sent to clients as URL safe strings with [Cursor::encode] and
+/// read back with [Cursor::decode].
+///
+/// # Examples
+/// ```rust,no_run
+/// # use agrum::{Cursor, QueryBook, ReadQueryBook, SqlEntity, Structured, Transaction, WhereCondition};
+/// # #[derive(SqlEntity, Structured)]
+/// # struct Contact {
+/// #     contact_id: uuid::Uuid,
+/// #     name: String,
+/// # }
+/// # struct ContactQueryBook;
+/// # impl QueryBook<Contact> for ContactQueryBook {
+/// #     fn get_sql_source(&self) -> &'static str {
+/// #         "pommr.contact"
+/// #     }
+/// # }
+/// # impl ReadQueryBook<Contact> for ContactQueryBook {}
+/// # struct Request {
+/// #     cursor: Option<String>,
+/// # }
+/// # async fn example(
+/// #     transaction: &Transaction<'_>,
+/// #     contact_book: &ContactQueryBook,
+/// #     request: Request,
+/// # ) -> agrum::Result<()> {
+/// let cursor = request.cursor.as_deref().map(Cursor::decode).transpose()?;
+/// let query = contact_book.paginate_after(WhereCondition::default(), cursor.as_ref(), &20)?;
+/// let page = transaction.query_page(query).await?;
+/// let next = page.next.map(|cursor| cursor.encode());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cursor {
+    direction: Direction,
+    values: Vec<Option<String>>,
+}
+
+impl Cursor {
+    /// Return the cursor as a URL safe string.
+    pub fn encode(&self) -> String {
+        let direction = match self.direction {
+            Direction::After => "after",
+            Direction::Before => "before",
+        };
+        let json = serde_json::json!([direction, self.values]);
+
+        URL_SAFE_NO_PAD.encode(json.to_string())
+    }
+
+    /// Read a cursor encoded with [Cursor::encode]. An [Error::InvalidCursor]
+    /// is returned if the string is not a cursor.
+    pub fn decode(encoded: &str) -> Result<Self> {
+        let invalid = |message: &str| Error::InvalidCursor(message.to_string());
+        let bytes = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|_| invalid("not base64 encoded"))?;
+        let json: Value = serde_json::from_slice(&bytes).map_err(|_| invalid("not JSON"))?;
+        let Some([direction, Value::Array(values)]) = json.as_array().map(Vec::as_slice) else {
+            return Err(invalid("unexpected structure"));
+        };
+        let direction = match direction.as_str() {
+            Some("after") => Direction::After,
+            Some("before") => Direction::Before,
+            _ => return Err(invalid("unknown direction")),
+        };
+        let values = values
+            .iter()
+            .map(|value| match value {
+                Value::String(value) => Ok(Some(value.clone())),
+                Value::Null => Ok(None),
+                _ => Err(invalid("values must be strings")),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { direction, values })
+    }
+}
+
+impl Display for Cursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.encode())
+    }
+}
+
+/// A page of entities and the cursors of the pages around it.
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    /// The entities of the page, in the keyset order.
+    pub items: Vec<T>,
+
+    /// Cursor of the following page, `None` on the last page.
+    pub next: Option<Cursor>,
+
+    /// Cursor of the preceding page, `None` on the first page.
+    pub previous: Option<Cursor>,
+}
+
+/// A keyset pagination query, see
+/// [ReadQueryBook::paginate_after](crate::ReadQueryBook::paginate_after). It
+/// fetches one row more than the page size to know if there is a page after
+/// it.
+pub struct PageQuery<'a, T: SqlEntity> {
+    query: SqlQuery<'a, T>,
+    keys: usize,
+    page_size: usize,
+    direction: Option<Direction>,
+}
+
+impl<'a, T: SqlEntity> PageQuery<'a, T> {
+    /// Create the page query from a query returning the keyset columns after
+    /// the projection, `page_size + 1` rows at most.
+    pub(crate) fn new(
+        query: SqlQuery<'a, T>,
+        keys: usize,
+        page_size: usize,
+        cursor: Option<&Cursor>,
+    ) -> Self {
+        Self {
+            query,
+            keys,
+            page_size,
+            direction: cursor.map(|cursor| cursor.direction),
+        }
+    }
+
+    /// Return the SQL query.
+    pub fn get_query(&self) -> &SqlQuery<'a, T> {
+        &self.query
+    }
+}
+
+impl Transaction<'_> {
+    /// Fetch the page of entities of the given keyset pagination query along
+    /// with the cursors of the next and previous pages.
+    /// If the query holds a [ConstraintErrorMap](crate::ConstraintErrorMap),
+    /// the database errors are mapped to the according domain errors.
+    pub async fn query_page<T: SqlEntity>(&self, query: PageQuery<'_, T>) -> Result<Page<T>> {
+        let PageQuery {
+            query,
+            keys,
+            page_size,
+            direction,
+        } = query;
+        let constraint_error_map = query.get_constraint_error_map().cloned();
+        let (statement, parameters) = query.expand();
+        let parameters: Vec<&(dyn ToSql + Sync)> = parameters
+            .into_iter()
+            .map(|p| p as &(dyn ToSql + Sync))
+            .collect();
+        let rows = self
+            .transaction
+            .query(&statement, &parameters)
+            .await
+            .map_err(|e| map_error(constraint_error_map.as_deref(), e))?;
+        let has_more = rows.len() > page_size;
+        let mut entries = rows
+            .iter()
+            .take(page_size)
+            .map(|row| Ok((T::hydrate(row)?, keyset_values(row, keys)?)))
+            .collect::<Result<Vec<_>>>()?;
+
+        if direction == Some(Direction::Before) {
+            entries.reverse();
+        }
+
+        let cursor = |entry: Option<&(T, Vec<Option<String>>)>, direction| {
+            entry.map(|(_, values)| Cursor {
+                direction,
+                values: values.clone(),
+            })
+        };
+        let first = cursor(entries.first(), Direction::Before);
+        let last = cursor(entries.last(), Direction::After);
+        let (previous, next) = match direction {
+            None => (None, last.filter(|_| has_more)),
+            Some(Direction::After) => (first, last.filter(|_| has_more)),
+            Some(Direction::Before) => (first.filter(|_| has_more), last),
+        };
+
+        Ok(Page {
+            items: entries.into_iter().map(|(entity, _)| entity).collect(),
+            next,
+            previous,
+        })
+    }
+}
+
+/// Return the keyset values of the row.
+fn keyset_values(row: &Row, keys: usize) -> Result<Vec<Option<String>>> {
+    (0..keys)
+        .map(|index| Ok(row.try_get(format!("{CURSOR_COLUMN}{index}").as_str())?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{HydrationError, Structure};
+
+    use super::*;
+
+    struct TestEntity;
+
+    impl Structured for TestEntity {
+        fn get_structure() -> Structure {
+            Structure::new(&[("id", "uuid"), ("name", "text"), ("score", "int")])
+                .with_primary_key(&["id"])
+        }
+    }
+
+    impl SqlEntity for TestEntity {
+        fn get_projection() -> Projection<Self> {
+            Projection::new("t").set_definition("name", "lower(t.name)")
+        }
+
+        fn hydrate(_row: &Row) -> std::result::Result<Self, HydrationError> {
+            Ok(Self)
+        }
+    }
+
+    fn cursor(direction: Direction) -> Cursor {
+        Cursor {
+            direction,
+            values: vec![Some("alice".to_string()), None],
+        }
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        let cursor = cursor(Direction::Before);
+        let encoded = cursor.encode();
+
+        assert!(
+            encoded
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        );
+        assert_eq!(cursor, Cursor::decode(&encoded).unwrap());
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        for encoded in [
+            "not a cursor",
+            &URL_SAFE_NO_PAD.encode("{}"),
+            &URL_SAFE_NO_PAD.encode(r#"["sideways", []]"#),
+            &URL_SAFE_NO_PAD.encode(r#"["after", [1]]"#),
+        ] {
+            assert!(matches!(
+                Cursor::decode(encoded),
+                Err(Error::InvalidCursor(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_primary_key() {
+        assert_eq!(
+            Keyset::new(&["id"], SortOrder::Asc),
+            Keyset::primary_key::<TestEntity>()
+        );
+    }
+
+    #[test]
+    fn test_first_page() {
+        let clauses = Keyset::new(&["name", "id"], SortOrder::Desc)
+            .expand(&TestEntity::get_projection(), None)
+            .unwrap();
+
+        assert_eq!(
+            "(lower(t.name))::text as agrum_cursor_0, (t.id)::text as agrum_cursor_1",
+            clauses.columns
+        );
+        assert_eq!("lower(t.name) desc, t.id desc", clauses.order_by);
+        assert!(clauses.condition.is_none());
+    }
+
+    #[test]
+    fn test_after_and_before() {
+        let keyset = Keyset::new(&["name", "id"], SortOrder::Asc);
+        let after = cursor(Direction::After);
+        let clauses = keyset
+            .expand(&TestEntity::get_projection(), Some(&after))
+            .unwrap();
+        let (condition, parameters) = clauses.condition.unwrap().expand();
+
+        assert_eq!(
+            "(lower(t.name), t.id) > (cast($?::text as text), cast($?::text as uuid))",
+            condition
+        );
+        assert_eq!(2, parameters.len());
+        assert_eq!("lower(t.name) asc, t.id asc", clauses.order_by);
+
+        let before = cursor(Direction::Before);
+        let clauses = keyset
+            .expand(&TestEntity::get_projection(), Some(&before))
+            .unwrap();
+
+        assert!(clauses.condition.unwrap().expand().0.contains(" < "));
+        assert_eq!("lower(t.name) desc, t.id desc", clauses.order_by);
+    }
+
+    #[test]
+    fn test_cursor_of_another_keyset() {
+        let cursor = cursor(Direction::After);
+
+        assert!(matches!(
+            Keyset::new(&["id"], SortOrder::Asc)
+                .expand(&TestEntity::get_projection(), Some(&cursor)),
+            Err(Error::InvalidCursor(_))
+        ));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unknown_field() {
+        let _ =
+            Keyset::new(&["unknown"], SortOrder::Asc).expand(&TestEntity::get_projection(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_empty_keyset() {
+        let _ = Keyset::new(&[], SortOrder::Asc);
+    }
+}
@@ -0,0 +1,321 @@
+use std::{
+    future::Future,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    str::FromStr,
+    time::Duration,
+};
+
+use bb8::{CustomizeConnection, PooledConnection};
+use bb8_postgres::PostgresConnectionManager;
+use tokio_postgres::{Client, Config, NoTls, error::Error as PgError};
+
+use crate::{
+    Error, Result, Transaction, TransactionBuilder, TransactionOptions, run_in_transaction,
+};
+
+type Manager = PostgresConnectionManager<NoTls>;
+
+/// A pool of database connections.
+/// It is cheap to clone, all the clones share the same connections.
+///
+/// # Examples
+/// ```rust,no_run
+/// # use agrum::Pool;
+/// # async fn example() -> agrum::Result<()> {
+/// let pool = Pool::builder("host=localhost user=app dbname=app")
+///     .max_size(20)
+///     .search_path(&["pommr", "public"])
+///     .application_name("billing")
+///     .build()
+///     .await?;
+/// let mut connection = pool.get().await?;
+/// let transaction = connection.transaction().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Pool {
+    pool: bb8::Pool<Manager>,
+}
+
+impl Pool {
+    /// Create a pool with the default settings from a connection string.
+    pub async fn new(dsn: &str) -> Result<Self> {
+        Self::builder(dsn).build().await
+    }
+
+    /// Create a builder to configure the pool from a connection string.
+    pub fn builder(dsn: &str) -> PoolBuilder {
+        PoolBuilder {
+            config: Config::from_str(dsn).map_err(Error::Connection),
+            builder: bb8::Pool::builder(),
+            initializer: ConnectionInitializer::default(),
+        }
+    }
+
+    /// Create a builder to configure the pool from a connection configuration.
+    pub fn builder_from_config(config: Config) -> PoolBuilder {
+        PoolBuilder {
+            config: Ok(config),
+            builder: bb8::Pool::builder(),
+            initializer: ConnectionInitializer::default(),
+        }
+    }
+
+    /// Take a connection from the pool. An [Error::PoolTimeout] is returned if
+    /// no connection is available before the connection timeout.
+    pub async fn get(&self) -> Result<Connection<'_>> {
+        let connection = self.pool.get().await?;
+
+        Ok(Connection { connection })
+    }
+
+    /// Run the given unit of work in a transaction on a pooled connection,
+    /// see [run_in_transaction].
+    pub async fn transaction<R, F>(&self, options: &TransactionOptions, f: F) -> Result<R>
+    where
+        F: AsyncFnMut(&Transaction<'_>) -> Result<R>,
+    {
+        run_in_transaction(self, options, f).await
+    }
+
+    /// Return the current usage of the pool.
+    pub fn statistics(&self) -> PoolStatistics {
+        let state = self.pool.state();
+        let statistics = state.statistics;
+
+        PoolStatistics {
+            connections: state.connections,
+            idle_connections: state.idle_connections,
+            gets: statistics.get_started,
+            gets_waited: statistics.get_waited,
+            gets_timed_out: statistics.get_timed_out,
+            wait_time: statistics.get_wait_time,
+            connections_created: statistics.connections_created,
+            connections_closed: statistics.connections_closed_broken
+                + statistics.connections_closed_invalid
+                + statistics.connections_closed_max_lifetime
+                + statistics.connections_closed_idle_timeout,
+        }
+    }
+}
+
+/// A builder for [Pool].
+pub struct PoolBuilder {
+    config: Result<Config>,
+    builder: bb8::Builder<Manager>,
+    initializer: ConnectionInitializer,
+}
+
+impl PoolBuilder {
+    /// Set the maximum number of connections of the pool.
+    pub fn max_size(mut self, max_size: u32) -> Self {
+        self.builder = self.builder.max_size(max_size);
+        self
+    }
+
+    /// Set the number of idle connections the pool tries to keep.
+    pub fn min_idle(mut self, min_idle: u32) -> Self {
+        self.builder = self.builder.min_idle(min_idle);
+        self
+    }
+
+    /// Set how long to wait for a connection before returning an
+    /// [Error::PoolTimeout].
+    pub fn connection_timeout(mut self, connection_timeout: Duration) -> Self {
+        self.builder = self.builder.connection_timeout(connection_timeout);
+        self
+    }
+
+    /// Set how long a connection may stay idle before being closed.
+    pub fn idle_timeout(mut self, idle_timeout: Duration) -> Self {
+        self.builder = self.builder.idle_timeout(idle_timeout);
+        self
+    }
+
+    /// Set how long a connection is kept before being closed.
+    pub fn max_lifetime(mut self, max_lifetime: Duration) -> Self {
+        self.builder = self.builder.max_lifetime(max_lifetime);
+        self
+    }
+
+    /// Set the schema search path of the connections. The schema names are
+    /// quoted identifiers so they are taken as is, case included.
+    pub fn search_path(mut self, schemas: &[&str]) -> Self {
+        let schemas: Vec<String> = schemas
+            .iter()
+            .map(|schema| format!("\"{}\"", schema.replace('"', "\"\"")))
+            .collect();
+        self.initializer.search_path = Some(schemas.join(", "));
+        self
+    }
+
+    /// Set the application name of the connections as shown in
+    /// `pg_stat_activity`.
+    pub fn application_name(mut self, application_name: &str) -> Self {
+        if let Ok(config) = self.config.as_mut() {
+            config.application_name(application_name);
+        }
+        self
+    }
+
+    /// Add a SQL statement run on each new connection, in declaration order.
+    pub fn on_connect(mut self, statement: &str) -> Self {
+        self.initializer.statements.push(statement.to_string());
+        self
+    }
+
+    /// Create the pool and open its initial connections.
+    pub async fn build(self) -> Result<Pool> {
+        let manager = PostgresConnectionManager::new(self.config?, NoTls);
+        let builder = if self.initializer.is_empty() {
+            self.builder
+        } else {
+            self.builder
+                .connection_customizer(Box::new(self.initializer))
+        };
+        let pool = builder.build(manager).await?;
+
+        Ok(Pool { pool })
+    }
+}
+
+/// Statistics about the usage of a [Pool].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStatistics {
+    /// Number of open connections.
+    pub connections: u32,
+
+    /// Number of idle connections.
+    pub idle_connections: u32,
+
+    /// Number of connections requested.
+    pub gets: u64,
+
+    /// Number of requests that had to wait for a connection.
+    pub gets_waited: u64,
+
+    /// Number of requests that timed out.
+    pub gets_timed_out: u64,
+
+    /// Total time spent waiting for connections.
+    pub wait_time: Duration,
+
+    /// Number of connections opened.
+    pub connections_created: u64,
+
+    /// Number of connections closed by the pool.
+    pub connections_closed: u64,
+}
+
+/// A connection taken from a [Pool]. It goes back to the pool when dropped.
+pub struct Connection<'a> {
+    connection: PooledConnection<'a, Manager>,
+}
+
+impl Connection<'_> {
+    /// Start a transaction with the server defaults.
+    pub async fn transaction(&mut self) -> Result<Transaction<'_>> {
+        self.build_transaction().start().await
+    }
+
+    /// Create a builder to start a transaction with specific options.
+    pub fn build_transaction(&mut self) -> TransactionBuilder<'_> {
+        Transaction::build(&mut self.connection)
+    }
+}
+
+impl Deref for Connection<'_> {
+    type Target = Client;
+
+    fn deref(&self) -> &Self::Target {
+        &self.connection
+    }
+}
+
+impl DerefMut for Connection<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.connection
+    }
+}
+
+/// Initialize the new connections of the pool.
+#[derive(Debug, Default)]
+struct ConnectionInitializer {
+    search_path: Option<String>,
+    statements: Vec<String>,
+}
+
+impl ConnectionInitializer {
+    fn is_empty(&self) -> bool {
+        self.search_path.is_none() && self.statements.is_empty()
+    }
+}
+
+impl CustomizeConnection<Client, PgError> for ConnectionInitializer {
+    fn on_acquire<'a>(
+        &'a self,
+        connection: &'a mut Client,
+    ) -> Pin<Box<dyn Future<Output = std::result::Result<(), PgError>> + Send + 'a>> {
+        Box::pin(async move {
+            if let Some(search_path) = &self.search_path {
+                connection
+                    .execute(
+                        "select set_config('search_path', $1, false)",
+                        &[search_path],
+                    )
+                    .await?;
+            }
+
+            for statement in &self.statements {
+                connection.batch_execute(statement).await?;
+            }
+
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_with_invalid_dsn() {
+        let builder = Pool::builder("host=localhost port=not_a_number");
+
+        assert!(matches!(builder.config, Err(Error::Connection(_))));
+    }
+
+    #[test]
+    fn search_path_quoting() {
+        let builder = Pool::builder("host=localhost").search_path(&["Sales, 2024", r#"a"b"#]);
+
+        assert_eq!(
+            Some(r#""Sales, 2024", "a""b""#),
+            builder.initializer.search_path.as_deref()
+        );
+    }
+
+    #[test]
+    fn builder_initializer() {
+        let builder = Pool::builder("host=localhost user=app")
+            .search_path(&["pommr", "public"])
+            .application_name("agrum")
+            .on_connect("set timezone to 'UTC'");
+
+        assert_eq!(
+            Some(r#""pommr", "public""#),
+            builder.initializer.search_path.as_deref()
+        );
+        assert_eq!(
+            vec!["set timezone to 'UTC'".to_string()],
+            builder.initializer.statements
+        );
+        assert_eq!(
+            Some("agrum"),
+            builder.config.unwrap().get_application_name()
+        );
+    }
+}
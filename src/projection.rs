@@ -114,6 +114,14 @@ where
         self.fields.iter().map(|f| f.name.to_owned()).collect()
     }
 
+    /// Return the SQL definition of the given field if it is in the projection.
+    pub fn get_definition(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|field| field.name == name)
+            .map(|field| field.definition.as_str())
+    }
+
     /// Return the underlying structure.
     pub fn get_structure(&self) -> &Structure {
         &self.structure
@@ -197,5 +205,10 @@ mod tests {
             String::from("test_id as test_id, initcap(something) as something, is_what as is_what"),
             projection.expand()
         );
+        assert_eq!(
+            Some("initcap(something)"),
+            projection.get_definition("something")
+        );
+        assert_eq!(None, projection.get_definition("unknown"));
     }
 }
@@ -1,6 +1,8 @@
-use std::{collections::HashMap, fmt::Display, marker::PhantomData};
+use std::{borrow::Cow, collections::HashMap, fmt::Display, marker::PhantomData, sync::Arc};
 
-use crate::{SqlEntity, ToSqlAny};
+use crate::{
+    CompiledTemplate, ConstraintErrorMap, OwnedParameter, Result, SqlCommand, SqlEntity, ToSqlAny,
+};
 
 /// A query builder.
 /// This is the main structure to build the SQL queries using a templating system.
@@ -11,9 +13,7 @@ use crate::{SqlEntity, ToSqlAny};
 /// The query is expanded using the `expand` method to get the SQL query and
 /// the parameters.
 pub struct SqlQuery<'a, T: SqlEntity> {
-    query: String,
-    parameters: Vec<&'a dyn ToSqlAny>,
-    variables: HashMap<&'a str, String>,
+    command: SqlCommand<'a>,
     _phantom: PhantomData<T>,
 }
 
@@ -23,10 +23,20 @@ impl<'a, T: SqlEntity> SqlQuery<'a, T> {
     /// placeholders.
     /// The default variable is the projection of the entity.
     pub fn new(query: &str) -> Self {
+        Self::with_command(SqlCommand::new(query))
+    }
+
+    /// Create a new query from a compiled template, the template is not parsed
+    /// again. The default variable is the projection of the entity.
+    pub fn from_template(template: &'a CompiledTemplate) -> Self {
+        Self::with_command(SqlCommand::from_template(template))
+    }
+
+    fn with_command(mut command: SqlCommand<'a>) -> Self {
+        command.set_variable("projection", &T::get_projection().to_string());
+
         Self {
-            query: query.to_string(),
-            parameters: Vec::new(),
-            variables: [("projection", T::get_projection().to_string())].into(),
+            command,
             _phantom: PhantomData,
         }
     }
@@ -34,67 +44,355 @@ impl<'a, T: SqlEntity> SqlQuery<'a, T> {
     /// Set a variable in the query. This variable will be replaced by its value
     /// in the query.
     pub fn set_variable(&mut self, name: &'a str, value: &str) -> &mut Self {
-        self.variables.insert(name, value.to_string());
+        self.command.set_variable(name, value);
+        self
+    }
+
+    /// Set a variable whose value holds its own `$?` parameters, see
+    /// [SqlCommand::set_fragment].
+    pub fn set_fragment(
+        &mut self,
+        name: &'a str,
+        value: &str,
+        parameters: Vec<&'a dyn ToSqlAny>,
+    ) -> &mut Self {
+        self.command.set_fragment(name, value, parameters);
+        self
+    }
+
+    /// Set a variable to the given sub-query, its parameters are merged with
+    /// the parameters of the query at the place the variable appears in the
+    /// template.
+    pub fn set_subquery(&mut self, name: &'a str, query: impl Into<SqlCommand<'a>>) -> &mut Self {
+        self.command.set_subquery(name, query);
         self
     }
 
     /// Add a parameter to the query. This parameter will be replaced by its
     /// value in the query. The parameter will be expanded in the `$?` placeholder.
     pub fn add_parameter(&mut self, parameter: &'a dyn ToSqlAny) -> &mut Self {
-        self.parameters.push(parameter);
+        self.command.add_parameter(parameter);
         self
     }
 
     /// Append a vec of parameters to the query.
     pub fn append_parameters(&mut self, parameters: Vec<&'a dyn ToSqlAny>) -> &mut Self {
-        self.parameters.extend(parameters);
+        self.command.append_parameters(parameters);
         self
     }
 
     /// Set the parameters of the query.
     pub fn set_parameters(&mut self, parameters: Vec<&'a dyn ToSqlAny>) -> &mut Self {
-        self.parameters = parameters;
+        self.command.set_parameters(parameters);
         self
     }
 
+    /// Bind a value to the named parameter. It will be expanded in all the
+    /// `$:name` placeholders.
+    pub fn set_named_parameter(&mut self, name: &'a str, parameter: &'a dyn ToSqlAny) -> &mut Self {
+        self.command.set_named_parameter(name, parameter);
+        self
+    }
+
+    /// Set the map used to turn the database errors raised by this query into
+    /// domain errors.
+    pub fn set_constraint_error_map(
+        &mut self,
+        constraint_error_map: Option<Arc<ConstraintErrorMap>>,
+    ) -> &mut Self {
+        self.command.set_constraint_error_map(constraint_error_map);
+        self
+    }
+
+    /// Return the map used to turn the database errors into domain errors.
+    pub fn get_constraint_error_map(&self) -> Option<&Arc<ConstraintErrorMap>> {
+        self.command.get_constraint_error_map()
+    }
+
     /// Return the variables of the query.
     pub fn get_variables(&self) -> &HashMap<&'a str, String> {
-        &self.variables
+        self.command.get_variables()
     }
 
-    /// Return the parameters of the query. This method is mostly intended for
-    /// testing purposes since the parameters are cloned.
+    /// Return the positional parameters of the query. This method is mostly
+    /// intended for testing purposes since the parameters are cloned.
     pub fn get_parameters(&self) -> Vec<&'a dyn ToSqlAny> {
-        self.parameters.clone()
+        self.command.get_parameters()
+    }
+
+    /// Return the named parameters of the query.
+    pub fn get_named_parameters(&self) -> &HashMap<&'a str, &'a dyn ToSqlAny> {
+        self.command.get_named_parameters()
     }
 
     /// Return the query and the parameters to be sent to the server.
     /// This consumes the query instance.
     pub fn expand(self) -> (String, Vec<&'a dyn ToSqlAny>) {
-        let query = self.to_string();
-        let parameters = self.parameters;
-        (query, parameters)
+        self.command.expand()
+    }
+
+    /// Return the query and the parameters to be sent to the server after
+    /// checking the template, see [SqlCommand::try_expand]. The projection
+    /// variable set by default may be left unused.
+    pub fn try_expand(self) -> Result<(String, Vec<&'a dyn ToSqlAny>)> {
+        self.command.try_expand_with_optional(&["projection"])
+    }
+
+    /// Turn the query into an [OwnedSqlQuery] with the same template,
+    /// variables and fragments, the positional and named parameters being
+    /// cloned.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use agrum::{OwnedSqlQuery, SqlEntity, SqlQuery, Structured};
+    /// # #[derive(SqlEntity, Structured)]
+    /// # struct Contact {
+    /// #     contact_id: uuid::Uuid,
+    /// #     name: String,
+    /// # }
+    /// fn contacts_of(company_id: uuid::Uuid) -> OwnedSqlQuery<Contact> {
+    ///     let mut query = SqlQuery::<Contact>::new(
+    ///         "select {:projection:} from pommr.contact where company_id = $:company_id",
+    ///     );
+    ///     query.set_named_parameter("company_id", &company_id);
+    ///
+    ///     query.into_owned()
+    /// }
+    ///
+    /// let query = contacts_of(uuid::Uuid::new_v4());
+    /// assert_eq!(
+    ///     "select contact_id as contact_id, name as name from pommr.contact where company_id = $1",
+    ///     SqlQuery::from(&query).to_string()
+    /// );
+    /// ```
+    pub fn into_owned(self) -> OwnedSqlQuery<T> {
+        let command = self.command;
+        let owned = |parameters: &[&dyn ToSqlAny]| -> Vec<OwnedParameter> {
+            parameters
+                .iter()
+                .map(|parameter| parameter.to_owned_parameter())
+                .collect()
+        };
+
+        OwnedSqlQuery {
+            template: Cow::Owned(command.get_template().clone()),
+            parameters: owned(&command.get_parameters()),
+            named_parameters: command
+                .get_named_parameters()
+                .iter()
+                .map(|(name, parameter)| (name.to_string(), parameter.to_owned_parameter()))
+                .collect(),
+            variables: command
+                .get_variables()
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.clone()))
+                .collect(),
+            fragments: command
+                .get_fragments()
+                .iter()
+                .map(|(name, (value, parameters))| {
+                    (name.to_string(), (value.clone(), owned(parameters)))
+                })
+                .collect(),
+            constraint_error_map: command.get_constraint_error_map().cloned(),
+            _phantom: PhantomData,
+        }
     }
 }
 
 impl<'a, T: SqlEntity> Display for SqlQuery<'a, T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut query = self.query.clone();
-        for (name, value) in &self.variables {
-            query = query.replace(&format!("{{:{name}:}}"), value);
+        self.command.fmt(f)
+    }
+}
+
+impl<'a, T: SqlEntity> From<SqlQuery<'a, T>> for SqlCommand<'a> {
+    /// Drop the entity type of the query, the returned rows are ignored.
+    fn from(query: SqlQuery<'a, T>) -> Self {
+        query.command
+    }
+}
+
+/// A query that owns its template variables and parameters.
+/// Unlike [SqlQuery], it has no lifetime so it can be returned by a function,
+/// stored or sent to another task. It is borrowed as a [SqlQuery] when given
+/// to a [Transaction](crate::Transaction).
+///
+/// A borrowed query is turned into an owned query with [SqlQuery::into_owned].
+///
+/// # Examples
+/// ```rust,no_run
+/// # use agrum::{OwnedSqlQuery, OwnedWhereCondition, Pool, SqlEntity, Structured, owned_params};
+/// # #[derive(SqlEntity, Structured)]
+/// # struct Contact {
+/// #     contact_id: uuid::Uuid,
+/// #     company_id: uuid::Uuid,
+/// # }
+/// fn contacts_of(company_id: uuid::Uuid) -> OwnedSqlQuery<Contact> {
+///     let condition = OwnedWhereCondition::new("company_id = $?", owned_params![company_id]);
+///     let (condition, parameters) = condition.expand();
+///     let mut query = OwnedSqlQuery::new("select {:projection:} from pommr.contact where {:condition:}");
+///     query
+///         .set_variable("condition", &condition)
+///         .set_parameters(parameters);
+///     query
+/// }
+///
+/// # async fn example(pool: Pool, company_id: uuid::Uuid) -> agrum::Result<()> {
+/// let query = contacts_of(company_id);
+/// let contacts = tokio::spawn(async move {
+///     let mut connection = pool.get().await?;
+///     let transaction = connection.transaction().await?;
+///     let contacts = transaction.query_all(&query).await?;
+///     transaction.commit().await?;
+///
+///     agrum::Result::Ok(contacts)
+/// })
+/// .await
+/// .expect("the task does not panic")?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct OwnedSqlQuery<T: SqlEntity> {
+    template: Cow<'static, CompiledTemplate>,
+    parameters: Vec<OwnedParameter>,
+    named_parameters: HashMap<String, OwnedParameter>,
+    variables: HashMap<String, String>,
+    fragments: HashMap<String, (String, Vec<OwnedParameter>)>,
+    constraint_error_map: Option<Arc<ConstraintErrorMap>>,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T: SqlEntity> OwnedSqlQuery<T> {
+    /// Create a new query using the given string as a SQL template.
+    /// The default variable is the projection of the entity.
+    pub fn new(query: &str) -> Self {
+        Self::with_template(Cow::Owned(CompiledTemplate::new(query)))
+    }
+
+    /// Create a new query from a compiled template, the template is not parsed
+    /// again. The default variable is the projection of the entity.
+    pub fn from_template(template: &'static CompiledTemplate) -> Self {
+        Self::with_template(Cow::Borrowed(template))
+    }
+
+    fn with_template(template: Cow<'static, CompiledTemplate>) -> Self {
+        Self {
+            template,
+            parameters: Vec::new(),
+            named_parameters: HashMap::new(),
+            variables: [("projection".to_string(), T::get_projection().to_string())].into(),
+            fragments: HashMap::new(),
+            constraint_error_map: None,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Set a variable in the query. This variable will be replaced by its value
+    /// in the query.
+    pub fn set_variable(&mut self, name: &str, value: &str) -> &mut Self {
+        self.fragments.remove(name);
+        self.variables.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Set a variable whose value holds its own `$?` parameters, they are
+    /// moved in the query. See [SqlCommand::set_fragment].
+    pub fn set_fragment(
+        &mut self,
+        name: &str,
+        value: &str,
+        parameters: Vec<OwnedParameter>,
+    ) -> &mut Self {
+        self.variables.remove(name);
+        self.fragments
+            .insert(name.to_string(), (value.to_string(), parameters));
+        self
+    }
+
+    /// Add a parameter to the query, it is moved in the query.
+    pub fn add_parameter(&mut self, parameter: impl ToSqlAny + Send) -> &mut Self {
+        self.parameters.push(Box::new(parameter));
+        self
+    }
+
+    /// Append a vec of parameters to the query.
+    pub fn append_parameters(&mut self, parameters: Vec<OwnedParameter>) -> &mut Self {
+        self.parameters.extend(parameters);
+        self
+    }
+
+    /// Set the parameters of the query.
+    pub fn set_parameters(&mut self, parameters: Vec<OwnedParameter>) -> &mut Self {
+        self.parameters = parameters;
+        self
+    }
+
+    /// Bind a value to the named parameter, it is moved in the query.
+    pub fn set_named_parameter(
+        &mut self,
+        name: &str,
+        parameter: impl ToSqlAny + Send,
+    ) -> &mut Self {
+        self.named_parameters
+            .insert(name.to_string(), Box::new(parameter));
+        self
+    }
+
+    /// Set the map used to turn the database errors raised by this query into
+    /// domain errors.
+    pub fn set_constraint_error_map(
+        &mut self,
+        constraint_error_map: Option<Arc<ConstraintErrorMap>>,
+    ) -> &mut Self {
+        self.constraint_error_map = constraint_error_map;
+        self
+    }
+
+    /// Return the variables of the query.
+    pub fn get_variables(&self) -> &HashMap<String, String> {
+        &self.variables
+    }
+}
+
+impl<'a, T: SqlEntity> From<&'a OwnedSqlQuery<T>> for SqlQuery<'a, T> {
+    /// Borrow the template, the variables and the parameters of the owned
+    /// query.
+    fn from(owned: &'a OwnedSqlQuery<T>) -> Self {
+        let borrowed = |parameters: &'a [OwnedParameter]| -> Vec<&'a dyn ToSqlAny> {
+            parameters
+                .iter()
+                .map(|parameter| parameter.as_ref() as &dyn ToSqlAny)
+                .collect()
+        };
+        let mut command = SqlCommand::from_template(&owned.template);
+
+        for (name, value) in &owned.variables {
+            command.set_variable(name, value);
         }
-        let mut param_index = 1;
-        //
-        // Replace parameters placeholders by numerated parameters.
-        loop {
-            if !query.contains("$?") {
-                break;
-            }
-            query = query.replacen("$?", &format!("${param_index}"), 1);
-            param_index += 1;
+
+        for (name, (value, parameters)) in &owned.fragments {
+            command.set_fragment(name, value, borrowed(parameters));
         }
 
-        write!(f, "{}", query)
+        for (name, parameter) in &owned.named_parameters {
+            command.set_named_parameter(name, parameter.as_ref() as &dyn ToSqlAny);
+        }
+
+        command
+            .set_parameters(borrowed(&owned.parameters))
+            .set_constraint_error_map(owned.constraint_error_map.clone());
+
+        Self {
+            command,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: SqlEntity> Display for OwnedSqlQuery<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SqlQuery::from(self).fmt(f)
     }
 }
 
@@ -118,7 +416,7 @@ mod tests {
             Projection::<TestSqlEntity>::default()
         }
 
-        fn hydrate(row: &Row) -> Result<Self, HydrationError> {
+        fn hydrate(row: &Row) -> std::result::Result<Self, HydrationError> {
             Ok(TestSqlEntity {
                 id: row.get("id"),
                 name: row.get("name"),
@@ -234,4 +532,89 @@ mod tests {
         let parameter: &i32 = (parameters[2] as &dyn Any).downcast_ref().unwrap();
         assert_eq!(parameter, &3_i32);
     }
+
+    #[test]
+    fn test_owned_query() {
+        fn build(id: i64) -> OwnedSqlQuery<TestSqlEntity> {
+            let mut query = OwnedSqlQuery::new(
+                "select {:projection:} from {:source:} where id = $? or parent_id = $:id",
+            );
+            query
+                .set_variable("source", "some_table")
+                .add_parameter(id)
+                .set_named_parameter("id", id);
+            query
+        }
+
+        let owned = build(5);
+        let (query, parameters) = SqlQuery::from(&owned).expand();
+        assert_eq!(
+            "select id as id, name as name from some_table where id = $1 or parent_id = $2",
+            query
+        );
+        assert_eq!(parameters.len(), 2);
+        let parameter: &i64 = (parameters[1] as &dyn Any).downcast_ref().unwrap();
+        assert_eq!(parameter, &5_i64);
+        assert_eq!(owned.to_string(), query);
+    }
+
+    #[test]
+    fn test_into_owned() {
+        let id = 5_i64;
+        let mut query = SqlQuery::<TestSqlEntity>::new(
+            "select {:projection:} from {:source:} where id = $? and parent_id = $:id and {:condition:}",
+        );
+        query
+            .set_variable("source", "some_table")
+            .set_fragment("condition", "name <> $?", params!["root"])
+            .set_named_parameter("id", &id)
+            .add_parameter(&id);
+        let expected = query.to_string();
+
+        let owned = query.into_owned();
+        let (query, parameters) = SqlQuery::from(&owned).expand();
+        assert_eq!(
+            "select id as id, name as name from some_table where id = $1 and parent_id = $2 and name <> $3",
+            query
+        );
+        assert_eq!(expected, query);
+        assert_eq!(parameters.len(), 3);
+        let parameter: &&str = (parameters[2] as &dyn Any).downcast_ref().unwrap();
+        assert_eq!(*parameter, "root");
+    }
+
+    #[test]
+    fn test_owned_query_runtime_names() {
+        let mut owned = OwnedSqlQuery::<TestSqlEntity>::new(
+            "select {:projection:} from {:source:} where {:field:} = $:value",
+        );
+        let (variable, parameter) = ("field".to_string(), "value".to_string());
+        owned
+            .set_variable("source", "some_table")
+            .set_variable(&variable, "name")
+            .set_named_parameter(&parameter, "root".to_string());
+
+        assert_eq!(
+            "select id as id, name as name from some_table where name = $1",
+            owned.to_string()
+        );
+    }
+
+    #[test]
+    fn test_try_expand_without_projection() {
+        let mut query = SqlQuery::<TestSqlEntity>::new("select count(*) from {:source:}");
+        query.set_variable("source", "some_table");
+
+        assert_eq!(
+            "select count(*) from some_table",
+            query.try_expand().unwrap().0
+        );
+    }
+
+    #[test]
+    fn test_owned_query_is_static_and_send() {
+        fn assert_static_send<T: Send + 'static>(_value: T) {}
+
+        assert_static_send(OwnedSqlQuery::<TestSqlEntity>::new("select 1"));
+    }
 }
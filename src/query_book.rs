@@ -1,6 +1,12 @@
-use std::{collections::HashMap, iter::repeat_n};
+use std::{collections::HashMap, iter::repeat_n, sync::Arc};
 
-use crate::{SqlEntity, SqlQuery, ToSqlAny, WhereCondition};
+use crate::{
+    CompiledTemplate, ConstraintErrorMap, Cursor, Keyset, PageQuery, SelectOptions, SqlEntity,
+    SqlQuery, ToSqlAny, Updates, WhereCondition,
+};
+
+/// Maximum number of parameters the server accepts in a single query.
+pub const MAX_QUERY_PARAMETERS: usize = 65_535;
 
 /// A trait to mark types that are query books.
 /// Query books are responsible of building the queries that will be sent to the
@@ -54,15 +60,24 @@ pub trait QueryBook<T: SqlEntity> {
     /// It could be a table name or a view name or a values list or function or
     /// even a sub-query.
     fn get_sql_source(&self) -> &'static str;
+
+    /// Return the map used to turn the database errors raised by the queries
+    /// of this book into domain errors. There is no map by default.
+    fn get_constraint_error_map(&self) -> Option<Arc<ConstraintErrorMap>> {
+        None
+    }
 }
 
 /// A trait that marks QueryBooks that perform simple `select {:projection:}
-/// from {:source:} where {:condition:}` queries.
+/// from {:source:} where {:condition:}` queries, optionally sorted and limited.
 pub trait ReadQueryBook<T: SqlEntity>: QueryBook<T> {
     /// Return the definition of the SQL query.
     /// It could be a select statement or an insert statement or a update statement or a delete statement.
+    /// The `{:order:}` and `{:limit:}` placeholders receive the sort and the
+    /// limit of [ReadQueryBook::select_with], they may be omitted if the
+    /// queries are never sorted nor limited.
     fn get_sql_definition(&self) -> &'static str {
-        "select {:projection:} from {:source:} where {:condition:}"
+        "select {:projection:} from {:source:} where {:condition:}{:order:}{:limit:}"
     }
 
     /// Create a new select query with the given conditions.
@@ -70,15 +85,110 @@ pub trait ReadQueryBook<T: SqlEntity>: QueryBook<T> {
     /// The projection will be the projection of the entity returned by the `get_projection` method.
     /// The source will be the source returned by the `get_sql_source` method.
     fn select<'a>(&self, conditions: WhereCondition<'a>) -> SqlQuery<'a, T> {
-        let mut query = SqlQuery::new(self.get_sql_definition());
+        self.select_with(conditions, SelectOptions::default())
+    }
+
+    /// Create a new select query with the given conditions, sorted and
+    /// limited according to the given options.
+    ///
+    /// It panics if a sorted field is not a field of the projection or if the
+    /// options sort or limit the rows and the definition has no `{:order:}` or
+    /// `{:limit:}` placeholder.
+    fn select_with<'a>(
+        &self,
+        conditions: WhereCondition<'a>,
+        options: SelectOptions<'a>,
+    ) -> SqlQuery<'a, T> {
+        let template = CompiledTemplate::cached(self.get_sql_definition());
+        let projection = T::get_projection();
+        let order_sql = options.expand_order(&projection);
+        let (limit_sql, limit_params) = options.expand_limit();
+        let mut query = SqlQuery::from_template(template);
         let (conditions, parameters) = conditions.expand();
         query
-            .set_variable("projection", &T::get_projection().to_string())
+            .set_variable("projection", &projection.to_string())
             .set_variable("source", self.get_sql_source())
             .set_variable("condition", &conditions.to_string())
+            .set_constraint_error_map(self.get_constraint_error_map())
             .set_parameters(parameters);
 
+        for (slot, sql, params) in [
+            ("order", order_sql, Vec::new()),
+            ("limit", limit_sql, limit_params),
+        ] {
+            if template.has_variable(slot) {
+                query.set_fragment(slot, &sql, params);
+            } else if !sql.is_empty() {
+                panic!("The SQL definition has no {{:{slot}:}} placeholder.");
+            }
+        }
+
+        query
+    }
+
+    /// Return the keyset the pages of [ReadQueryBook::paginate_after] are
+    /// sorted by. It is the primary key of the entity structure by default.
+    fn get_keyset(&self) -> Keyset {
+        Keyset::primary_key::<T>()
+    }
+
+    /// Definition of the keyset pagination query.
+    /// The `{:keyset_order:}` placeholder receives the fields of the keyset
+    /// with their sort order and `{:page_limit:}` the bound number of rows to
+    /// fetch.
+    fn get_page_sql_definition(&self) -> &'static str {
+        "select {:projection:}, {:cursor:} from {:source:} where {:condition:} order by {:keyset_order:} limit {:page_limit:}"
+    }
+
+    /// Create a keyset pagination query returning the page of `page_size`
+    /// entities matching the given conditions that follows the cursor, the
+    /// first page if there is no cursor. Cursors of the previous pages return
+    /// the page preceding them. The page is fetched with
+    /// [Transaction::query_page](crate::Transaction::query_page) which returns
+    /// the cursors of the pages around it. The page size is sent as a
+    /// parameter, like the limit of [SelectOptions].
+    /// An [Error::InvalidCursor](crate::Error::InvalidCursor) is returned if
+    /// the cursor was not created for the keyset of this book.
+    ///
+    /// It panics if the page size is not greater than zero, if a field of the
+    /// keyset is not a field of the projection or, with the default
+    /// [ReadQueryBook::get_keyset], if the structure declares no primary key.
+    fn paginate_after<'a>(
+        &self,
+        conditions: WhereCondition<'a>,
+        cursor: Option<&'a Cursor>,
+        page_size: &'a i64,
+    ) -> crate::Result<PageQuery<'a, T>> {
+        if *page_size <= 0 {
+            panic!("The page size must be greater than zero.");
+        }
+
+        let keyset = self.get_keyset();
+        let projection = T::get_projection();
+        let clauses = keyset.expand(&projection, cursor)?;
+        let conditions = match clauses.condition {
+            Some(condition) => conditions.and_where(condition),
+            None => conditions,
+        };
+        let (conditions, parameters) = conditions.expand();
+        let mut query =
+            SqlQuery::from_template(CompiledTemplate::cached(self.get_page_sql_definition()));
         query
+            .set_variable("projection", &projection.to_string())
+            .set_variable("cursor", &clauses.columns)
+            .set_variable("source", self.get_sql_source())
+            .set_variable("condition", &conditions)
+            .set_variable("keyset_order", &clauses.order_by)
+            .set_constraint_error_map(self.get_constraint_error_map())
+            .set_parameters(parameters)
+            .set_fragment("page_limit", "$?::bigint + 1", vec![page_size]);
+
+        Ok(PageQuery::new(
+            query,
+            keyset.get_fields().len(),
+            *page_size as usize,
+            cursor,
+        ))
     }
 }
 
@@ -97,12 +207,14 @@ pub trait DeleteQueryBook<T: SqlEntity>: QueryBook<T> {
     /// The conditions will be the conditions passed to the method.
     /// The projection will be the projection of the entity returned by the `get_projection` method.
     fn delete<'a>(&self, conditions: WhereCondition<'a>) -> SqlQuery<'a, T> {
-        let mut query = SqlQuery::new(self.get_sql_definition());
+        let mut query =
+            SqlQuery::from_template(CompiledTemplate::cached(self.get_sql_definition()));
         let (conditions, parameters) = conditions.expand();
         query
             .set_variable("source", self.get_sql_source())
             .set_variable("condition", &conditions.to_string())
             .set_variable("projection", &T::get_projection().to_string())
+            .set_constraint_error_map(self.get_constraint_error_map())
             .set_parameters(parameters);
         query
     }
@@ -119,31 +231,31 @@ pub trait UpdateQueryBook<T: SqlEntity>: QueryBook<T> {
     /// Create a new update query with the given updates and conditions.
     /// The query will be built using the definition returned by the `get_sql_definition` method.
     /// The source will be the source returned by the `get_sql_source` method.
-    /// The updates will be the updates passed to the method.
+    /// The updates will be the updates passed to the method, either an
+    /// [Updates] builder or a map of values. They are set in the entity
+    /// structure order.
     /// The conditions will be the conditions passed to the method.
     /// The projection will be the projection of the entity returned by the `get_projection` method.
+    ///
+    /// It panics if an updated column is not declared in the entity structure
+    /// or if there is no column to update.
     fn update<'a>(
         &self,
-        updates: HashMap<&'a str, &'a dyn ToSqlAny>,
+        updates: impl Into<Updates<'a>>,
         conditions: WhereCondition<'a>,
     ) -> SqlQuery<'a, T> {
+        let structure = <T as crate::Structured>::get_structure();
+        let (updates_sql, params) = updates.into().expand(&structure);
         let (condition_sql, condition_params) = conditions.expand();
-        let mut updates_fragments = Vec::with_capacity(updates.len());
-        let mut params: Vec<&'a dyn ToSqlAny> =
-            Vec::with_capacity(updates.len() + condition_params.len());
-
-        for (column, value) in updates {
-            updates_fragments.push(format!("{column} = $?"));
-            params.push(value);
-        }
-        let updates_sql = updates_fragments.join(", ");
 
-        let mut query = SqlQuery::new(self.get_sql_definition());
+        let mut query =
+            SqlQuery::from_template(CompiledTemplate::cached(self.get_sql_definition()));
         query
             .set_variable("source", self.get_sql_source())
             .set_variable("updates", &updates_sql)
             .set_variable("condition", &condition_sql)
             .set_variable("projection", &T::get_projection().to_string())
+            .set_constraint_error_map(self.get_constraint_error_map())
             .set_parameters(params)
             .append_parameters(condition_params);
 
@@ -165,40 +277,373 @@ pub trait InsertQueryBook<T: SqlEntity>: QueryBook<T> {
     /// The structure will be the structure of the entity returned by the `get_structure` method.
     /// The values will be the values passed to the method.
     /// The projection will be the projection of the entity returned by the `get_projection` method.
+    ///
+    /// It panics if a column is not declared in the entity structure.
     fn insert<'a>(&self, values: HashMap<&'a str, &'a dyn ToSqlAny>) -> SqlQuery<'a, T> {
         // Build column list and parameter list following the entity structure
+        let (columns, params) = insert_values::<T>(&values);
+        let columns_sql = columns.join(", ");
+        let values_sql = repeat_n("$?", columns.len()).collect::<Vec<_>>().join(", ");
+
+        let mut query =
+            SqlQuery::from_template(CompiledTemplate::cached(self.get_sql_definition()));
+        query
+            .set_variable("source", self.get_sql_source())
+            .set_variable("structure", &columns_sql)
+            .set_variable("values", &values_sql)
+            .set_variable("projection", &T::get_projection().to_string())
+            .set_constraint_error_map(self.get_constraint_error_map())
+            .set_parameters(params);
+
+        query
+    }
+
+    /// Definition of the multi-rows insert query.
+    fn get_bulk_sql_definition(&self) -> &'static str {
+        "insert into {:source:} ({:structure:}) values {:values:} returning {:projection:}"
+    }
+
+    /// Create the insert queries for the given rows of values.
+    /// The rows are inserted with multi-rows `values (…), (…)` statements. The
+    /// inserted columns are all the columns given in any row, in the entity
+    /// structure order, the columns missing in a row being set to their
+    /// `default` value. The rows are split in as many queries as needed to keep
+    /// each query under the server limit of [MAX_QUERY_PARAMETERS] parameters.
+    /// Each query returns the projection of the rows it inserts. There is no
+    /// query when there is no row.
+    /// Entities are not accepted since they hold all the fields, including the
+    /// ones generated by the database like the primary key. They can be loaded
+    /// with [Transaction::copy_in](crate::Transaction::copy_in) instead.
+    ///
+    /// It panics if a column is not declared in the entity structure or if
+    /// there are rows but no column to insert.
+    fn insert_many<'a, I>(&self, rows: I) -> Vec<SqlQuery<'a, T>>
+    where
+        I: IntoIterator<Item = HashMap<&'a str, &'a dyn ToSqlAny>>,
+    {
         let structure = <T as crate::Structured>::get_structure();
+        let names = structure.get_names();
+        let rows: Vec<HashMap<&'a str, &'a dyn ToSqlAny>> = rows.into_iter().collect();
 
-        let mut columns: Vec<&str> = Vec::new();
-        let mut params: Vec<&'a dyn ToSqlAny> = Vec::new();
+        if rows.is_empty() {
+            return Vec::new();
+        }
 
-        for name in structure.get_names() {
-            if let Some(value) = values.get(name) {
-                columns.push(name);
-                params.push(*value);
-            }
+        check_columns(&names, rows.iter().flat_map(|row| row.keys()));
+
+        let columns: Vec<&str> = names
+            .into_iter()
+            .filter(|name| rows.iter().any(|row| row.contains_key(name)))
+            .collect();
+
+        if columns.is_empty() {
+            panic!("No column to insert.");
         }
 
+        let columns_sql = columns.join(", ");
+        let projection = T::get_projection().to_string();
+        let rows_per_query = MAX_QUERY_PARAMETERS / columns.len();
+
+        rows.chunks(rows_per_query)
+            .map(|chunk| {
+                let mut params: Vec<&'a dyn ToSqlAny> = Vec::new();
+                let values_sql = chunk
+                    .iter()
+                    .map(|row| {
+                        let values: Vec<&str> = columns
+                            .iter()
+                            .map(|column| match row.get(column) {
+                                Some(value) => {
+                                    params.push(*value);
+                                    "$?"
+                                }
+                                None => "default",
+                            })
+                            .collect();
+
+                        format!("({})", values.join(", "))
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                let mut query = SqlQuery::from_template(CompiledTemplate::cached(
+                    self.get_bulk_sql_definition(),
+                ));
+                query
+                    .set_variable("source", self.get_sql_source())
+                    .set_variable("structure", &columns_sql)
+                    .set_variable("values", &values_sql)
+                    .set_variable("projection", &projection)
+                    .set_constraint_error_map(self.get_constraint_error_map())
+                    .set_parameters(params);
+
+                query
+            })
+            .collect()
+    }
+
+    /// Definition of the upsert query.
+    fn get_upsert_sql_definition(&self) -> &'static str {
+        "insert into {:source:} ({:structure:}) values ({:values:}) on conflict {:conflict:} returning {:projection:}"
+    }
+
+    /// Create a new insert query that resolves the conflicts on the given
+    /// target with the given action.
+    /// The values are handled the same way as the `insert` method does.
+    /// The `UpdateAll` action updates all the inserted columns that are not part
+    /// of the conflict target nor of the primary key.
+    /// Note that rows left untouched by the `DoNothing` action are not returned.
+    /// It panics if an inserted or updated column is not declared in the
+    /// entity structure or if there is no column left to update.
+    fn upsert<'a>(
+        &self,
+        values: HashMap<&'a str, &'a dyn ToSqlAny>,
+        target: ConflictTarget<'a>,
+        action: ConflictAction<'a>,
+    ) -> SqlQuery<'a, T> {
+        let structure = <T as crate::Structured>::get_structure();
+        let (columns, mut params) = insert_values::<T>(&values);
         let columns_sql = columns.join(", ");
         let values_sql = repeat_n("$?", columns.len()).collect::<Vec<_>>().join(", ");
 
-        let mut query = SqlQuery::new(self.get_sql_definition());
+        let target_sql = match &target {
+            ConflictTarget::Columns(target_columns) => format!("({})", target_columns.join(", ")),
+            ConflictTarget::Constraint(constraint) => format!("on constraint {constraint}"),
+        };
+        let updated_columns: Vec<&str> = match &action {
+            ConflictAction::DoNothing => Vec::new(),
+            ConflictAction::UpdateAll => {
+                let primary_key = structure.get_primary_key();
+                let target_columns = match &target {
+                    ConflictTarget::Columns(target_columns) => target_columns.as_slice(),
+                    ConflictTarget::Constraint(_) => &[],
+                };
+
+                columns
+                    .iter()
+                    .copied()
+                    .filter(|c| !primary_key.contains(c) && !target_columns.contains(c))
+                    .collect()
+            }
+            ConflictAction::Update { columns, .. } => {
+                let names = structure.get_names();
+
+                for column in columns {
+                    if !names.contains(column) {
+                        panic!(
+                            "Field {column} not found in structure. Available fields: '{}'.",
+                            names.join(", ")
+                        );
+                    }
+                }
+
+                columns.clone()
+            }
+        };
+        let conflict_sql = match action {
+            ConflictAction::DoNothing => format!("{target_sql} do nothing"),
+            ConflictAction::UpdateAll | ConflictAction::Update { .. }
+                if updated_columns.is_empty() =>
+            {
+                panic!("No column left to update on conflict {target_sql}.");
+            }
+            ConflictAction::UpdateAll => {
+                format!(
+                    "{target_sql} do update set {}",
+                    excluded_updates(&updated_columns)
+                )
+            }
+            ConflictAction::Update { condition, .. } => {
+                let updates_sql = excluded_updates(&updated_columns);
+
+                match condition {
+                    Some(condition) => {
+                        let (condition_sql, condition_params) = condition.expand();
+                        params.extend(condition_params);
+
+                        format!("{target_sql} do update set {updates_sql} where {condition_sql}")
+                    }
+                    None => format!("{target_sql} do update set {updates_sql}"),
+                }
+            }
+        };
+
+        let mut query =
+            SqlQuery::from_template(CompiledTemplate::cached(self.get_upsert_sql_definition()));
         query
             .set_variable("source", self.get_sql_source())
             .set_variable("structure", &columns_sql)
             .set_variable("values", &values_sql)
+            .set_variable("conflict", &conflict_sql)
             .set_variable("projection", &T::get_projection().to_string())
+            .set_constraint_error_map(self.get_constraint_error_map())
             .set_parameters(params);
 
         query
     }
 }
 
+/// Conflict target of an upsert query.
+#[derive(Debug, Clone)]
+pub enum ConflictTarget<'a> {
+    /// Conflict on the given columns, there must be a unique index on them.
+    Columns(Vec<&'a str>),
+
+    /// Conflict on the given unique or exclusion constraint.
+    Constraint(&'a str),
+}
+
+/// Action performed by an upsert query when the inserted row conflicts with an
+/// existing one.
+#[derive(Debug, Clone)]
+pub enum ConflictAction<'a> {
+    /// Leave the existing row untouched.
+    DoNothing,
+
+    /// Update all the non key columns of the existing row with the inserted
+    /// values.
+    UpdateAll,
+
+    /// Update the given columns of the existing row with the inserted values.
+    Update {
+        /// Columns to update.
+        columns: Vec<&'a str>,
+        /// Only update the existing rows matching this condition.
+        condition: Option<WhereCondition<'a>>,
+    },
+}
+
+/// Panic if a column is not one of the given structure fields.
+fn check_columns<'c>(names: &[&str], columns: impl IntoIterator<Item = &'c &'c str>) {
+    for column in columns {
+        if !names.contains(column) {
+            panic!(
+                "Field {column} not found in structure. Available fields: '{}'.",
+                names.join(", ")
+            );
+        }
+    }
+}
+
+/// Return the columns and the parameters of the given values in the entity
+/// structure order.
+///
+/// It panics if a column is not declared in the entity structure.
+fn insert_values<'a, T: SqlEntity>(
+    values: &HashMap<&'a str, &'a dyn ToSqlAny>,
+) -> (Vec<&'a str>, Vec<&'a dyn ToSqlAny>) {
+    let structure = <T as crate::Structured>::get_structure();
+    let names = structure.get_names();
+    let mut columns: Vec<&'a str> = Vec::new();
+    let mut params: Vec<&'a dyn ToSqlAny> = Vec::new();
+
+    check_columns(&names, values.keys());
+
+    for name in names {
+        if let Some((column, value)) = values.get_key_value(name) {
+            columns.push(column);
+            params.push(*value);
+        }
+    }
+
+    (columns, params)
+}
+
+/// Return the `set` clause updating the given columns from the `excluded` row.
+fn excluded_updates(columns: &[&str]) -> String {
+    columns
+        .iter()
+        .map(|column| format!("{column} = excluded.{column}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// A trait that brings primary key based queries to QueryBooks. The primary
+/// key is the one declared in the entity structure (see
+/// [Structure::with_primary_key](crate::Structure::with_primary_key)) unless
+/// the book overrides [PrimaryKeyQueryBook::get_primary_key], typically when
+/// its entity only projects some fields of the table.
+/// The key values are given in the primary key order, composite keys being
+/// expressed with several values.
+///
+/// Each query is available as soon as the QueryBook implements the according
+/// query trait: `find_by_pk` requires [ReadQueryBook], `update_by_pk` requires
+/// [UpdateQueryBook] and `delete_by_pk` requires [DeleteQueryBook].
+pub trait PrimaryKeyQueryBook<T: SqlEntity>: QueryBook<T> {
+    /// Return the fields of the primary key of the SQL source. It is the
+    /// primary key of the entity structure by default.
+    fn get_primary_key(&self) -> Vec<String> {
+        <T as crate::Structured>::get_structure()
+            .get_primary_key()
+            .into_iter()
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Create the condition matching the given primary key values.
+    /// It panics if the book has no primary key or if the number of values
+    /// does not match the primary key.
+    fn get_primary_key_condition<'a>(&self, key: Vec<&'a dyn ToSqlAny>) -> WhereCondition<'a> {
+        let primary_key = self.get_primary_key();
+
+        if primary_key.is_empty() {
+            panic!(
+                "No primary key declared in the structure of {} nor by its query book.",
+                std::any::type_name::<T>()
+            );
+        }
+        if primary_key.len() != key.len() {
+            panic!(
+                "Primary key ({}) expects {} values, {} given.",
+                primary_key.join(", "),
+                primary_key.len(),
+                key.len()
+            );
+        }
+
+        primary_key.into_iter().zip(key).fold(
+            WhereCondition::default(),
+            |condition, (field, value)| {
+                condition.and_where(WhereCondition::new(&format!("{field} = $?"), vec![value]))
+            },
+        )
+    }
+
+    /// Create a select query returning the entity with the given primary key.
+    fn find_by_pk<'a>(&self, key: Vec<&'a dyn ToSqlAny>) -> SqlQuery<'a, T>
+    where
+        Self: ReadQueryBook<T>,
+    {
+        ReadQueryBook::select(self, self.get_primary_key_condition(key))
+    }
+
+    /// Create an update query on the entity with the given primary key, the
+    /// updates being given like in [UpdateQueryBook::update].
+    fn update_by_pk<'a>(
+        &self,
+        updates: impl Into<Updates<'a>>,
+        key: Vec<&'a dyn ToSqlAny>,
+    ) -> SqlQuery<'a, T>
+    where
+        Self: UpdateQueryBook<T>,
+    {
+        UpdateQueryBook::update(self, updates, self.get_primary_key_condition(key))
+    }
+
+    /// Create a delete query on the entity with the given primary key.
+    fn delete_by_pk<'a>(&self, key: Vec<&'a dyn ToSqlAny>) -> SqlQuery<'a, T>
+    where
+        Self: DeleteQueryBook<T>,
+    {
+        DeleteQueryBook::delete(self, self.get_primary_key_condition(key))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::{any::Any, collections::HashMap, marker::PhantomData};
 
-    use crate::{Projection, Structure, Structured};
+    use crate::{Projection, SortOrder, Structure, Structured};
 
     use super::*;
 
@@ -231,6 +676,7 @@ mod tests {
                 ("score", "integer"),
                 ("is_active", "bool"),
             ])
+            .with_primary_key(&["id"])
         }
     }
 
@@ -259,6 +705,81 @@ mod tests {
 
     impl InsertQueryBook<Entity> for EntityQueryBook {}
 
+    impl PrimaryKeyQueryBook<Entity> for EntityQueryBook {}
+
+    struct Membership {
+        _group_id: u32,
+        _user_id: u32,
+        _role: String,
+    }
+
+    impl SqlEntity for Membership {
+        fn get_projection() -> Projection<Self> {
+            Projection::default()
+        }
+
+        fn hydrate(row: &tokio_postgres::Row) -> Result<Self, crate::HydrationError> {
+            Ok(Membership {
+                _group_id: row.get("group_id"),
+                _user_id: row.get("user_id"),
+                _role: row.get("role"),
+            })
+        }
+    }
+
+    impl Structured for Membership {
+        fn get_structure() -> Structure {
+            Structure::new(&[
+                ("group_id", "integer"),
+                ("user_id", "integer"),
+                ("role", "text"),
+            ])
+            .with_primary_key(&["group_id", "user_id"])
+        }
+    }
+
+    struct MembershipQueryBook;
+
+    impl QueryBook<Membership> for MembershipQueryBook {
+        fn get_sql_source(&self) -> &'static str {
+            "some_schema.membership"
+        }
+    }
+
+    impl ReadQueryBook<Membership> for MembershipQueryBook {}
+
+    impl PrimaryKeyQueryBook<Membership> for MembershipQueryBook {}
+
+    struct MappedEntityQueryBook;
+
+    impl QueryBook<Entity> for MappedEntityQueryBook {
+        fn get_sql_source(&self) -> &'static str {
+            "some_schema.entity_table"
+        }
+
+        fn get_constraint_error_map(&self) -> Option<Arc<ConstraintErrorMap>> {
+            Some(Arc::new(
+                ConstraintErrorMap::new().on_unique_violation(|_| std::fmt::Error),
+            ))
+        }
+    }
+
+    impl ReadQueryBook<Entity> for MappedEntityQueryBook {}
+
+    impl DeleteQueryBook<Entity> for MappedEntityQueryBook {}
+
+    #[test]
+    fn test_constraint_error_map() {
+        let query = EntityQueryBook::default().select(WhereCondition::default());
+        assert!(query.get_constraint_error_map().is_none());
+
+        let query = MappedEntityQueryBook.select(WhereCondition::default());
+        assert!(query.get_constraint_error_map().is_some());
+
+        let query = MappedEntityQueryBook.delete(WhereCondition::default());
+        assert!(query.get_constraint_error_map().is_some());
+    }
+
     #[test]
     fn test_select() {
         let query = EntityQueryBook::default().select(WhereCondition::new("id = $?", vec![&1_u32]));
@@ -272,6 +793,74 @@ mod tests {
         assert_eq!(parameter, &1_u32);
     }
 
+    #[test]
+    fn test_select_with() {
+        let limit = 10_i64;
+        let query = EntityQueryBook::default().select_with(
+            WhereCondition::new("id > $?", vec![&1_u32]),
+            SelectOptions::new()
+                .order_by("score", SortOrder::Desc)
+                .order_by("id", SortOrder::Asc)
+                .limit(&limit),
+        );
+        let (sql, parameters) = query.try_expand().unwrap();
+        assert_eq!(
+            sql,
+            "select entity_table.id as id, entity_table.name as name, entity_table.score as score, entity_table.is_active as is_active from some_schema.entity_table where id > $1 order by score desc, id asc limit $2"
+        );
+        let parameter: &i64 = (parameters[1] as &dyn Any).downcast_ref().unwrap();
+        assert_eq!(parameter, &10_i64);
+    }
+
+    #[test]
+    fn test_paginate_after() {
+        let cursor = Cursor::decode("WyJhZnRlciIsWyI0MiJdXQ").unwrap();
+        let query = EntityQueryBook::default()
+            .paginate_after(
+                WhereCondition::new("is_active", Vec::new()),
+                Some(&cursor),
+                &20,
+            )
+            .unwrap();
+        let parameters = query.get_query().get_parameters();
+        assert_eq!(
+            query.get_query().to_string(),
+            "select entity_table.id as id, entity_table.name as name, entity_table.score as score, entity_table.is_active as is_active, (entity_table.id)::text as agrum_cursor_0 from some_schema.entity_table where is_active and (entity_table.id) > (cast($1::text as integer)) order by entity_table.id asc limit $2::bigint + 1"
+        );
+        let parameter: &Option<String> = (parameters[0] as &dyn Any).downcast_ref().unwrap();
+        assert_eq!(parameter.as_deref(), Some("42"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_paginate_empty_page() {
+        let _ = EntityQueryBook::default().paginate_after(WhereCondition::default(), None, &0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_select_with_missing_slot() {
+        struct UnsortedEntityQueryBook;
+
+        impl QueryBook<Entity> for UnsortedEntityQueryBook {
+            fn get_sql_source(&self) -> &'static str {
+                "some_schema.entity_table"
+            }
+        }
+
+        impl ReadQueryBook<Entity> for UnsortedEntityQueryBook {
+            fn get_sql_definition(&self) -> &'static str {
+                "select {:projection:} from {:source:} where {:condition:}"
+            }
+        }
+
+        let query = UnsortedEntityQueryBook.select(WhereCondition::default());
+        assert!(query.try_expand().is_ok());
+
+        let _query = UnsortedEntityQueryBook
+            .select_with(WhereCondition::default(), SelectOptions::new().limit(&10));
+    }
+
     #[test]
     fn test_update() {
         let updates = HashMap::from([("name", &"test_name" as &dyn ToSqlAny)]);
@@ -289,6 +878,41 @@ mod tests {
         assert_eq!(parameter, &1_u32);
     }
 
+    #[test]
+    fn test_update_in_structure_order() {
+        let updates = Updates::from(HashMap::from([
+            ("is_active", &true as &dyn ToSqlAny),
+            ("name", &"test_name"),
+        ]))
+        .set_expression_with("score", "score + $?", vec![&1_i32]);
+        let query = EntityQueryBook::default()
+            .update(updates, WhereCondition::new("id = $?", vec![&1_u32]));
+        assert!(query.to_string().starts_with(
+            "update some_schema.entity_table set name = $1, score = score + $2, is_active = $3 where id = $4 returning "
+        ));
+        let parameters = query.get_parameters();
+        assert_eq!(parameters.len(), 4);
+        let parameter: &bool = (parameters[2] as &dyn Any).downcast_ref().unwrap();
+        assert!(parameter);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_update_unknown_column() {
+        let updates = HashMap::from([("unknown", &"test_name" as &dyn ToSqlAny)]);
+        let _query = EntityQueryBook::default().update(updates, WhereCondition::default());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_unknown_column() {
+        let values = HashMap::from([
+            ("name", &"test_name" as &dyn ToSqlAny),
+            ("unknown", &"whatever"),
+        ]);
+        let _query = EntityQueryBook::default().insert(values);
+    }
+
     #[test]
     fn test_delete() {
         let query = EntityQueryBook::default().delete(WhereCondition::new("id = $?", vec![&1_u32]));
@@ -302,6 +926,245 @@ mod tests {
         assert_eq!(parameter, &1_u32);
     }
 
+    #[test]
+    fn test_find_by_pk() {
+        let query = EntityQueryBook::default().find_by_pk(vec![&1_u32]);
+        assert_eq!(
+            query.to_string(),
+            "select entity_table.id as id, entity_table.name as name, entity_table.score as score, entity_table.is_active as is_active from some_schema.entity_table where id = $1"
+        );
+        let parameters = query.get_parameters();
+        assert_eq!(parameters.len(), 1);
+        let parameter: &u32 = (parameters[0] as &dyn Any).downcast_ref().unwrap();
+        assert_eq!(parameter, &1_u32);
+    }
+
+    #[test]
+    fn test_find_by_composite_pk() {
+        let query = MembershipQueryBook.find_by_pk(vec![&1_u32, &2_u32]);
+        assert_eq!(
+            query.to_string(),
+            "select group_id as group_id, user_id as user_id, role as role from some_schema.membership where group_id = $1 and user_id = $2"
+        );
+        let parameters = query.get_parameters();
+        assert_eq!(parameters.len(), 2);
+        let parameter: &u32 = (parameters[1] as &dyn Any).downcast_ref().unwrap();
+        assert_eq!(parameter, &2_u32);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_find_by_pk_wrong_key_length() {
+        let _query = MembershipQueryBook.find_by_pk(vec![&1_u32]);
+    }
+
+    #[test]
+    fn test_find_by_book_pk() {
+        struct Score;
+
+        impl SqlEntity for Score {
+            fn get_projection() -> Projection<Self> {
+                Projection::new("entity_table")
+            }
+
+            fn hydrate(_row: &tokio_postgres::Row) -> Result<Self, crate::HydrationError> {
+                Ok(Score)
+            }
+        }
+
+        impl Structured for Score {
+            fn get_structure() -> Structure {
+                Structure::new(&[("score", "integer")])
+            }
+        }
+
+        struct ScoreQueryBook;
+
+        impl QueryBook<Score> for ScoreQueryBook {
+            fn get_sql_source(&self) -> &'static str {
+                "some_schema.entity_table"
+            }
+        }
+
+        impl ReadQueryBook<Score> for ScoreQueryBook {}
+
+        impl PrimaryKeyQueryBook<Score> for ScoreQueryBook {
+            fn get_primary_key(&self) -> Vec<String> {
+                vec!["id".to_string()]
+            }
+        }
+
+        let query = ScoreQueryBook.find_by_pk(vec![&1_u32]);
+        assert_eq!(
+            query.to_string(),
+            "select entity_table.score as score from some_schema.entity_table where id = $1"
+        );
+    }
+
+    #[test]
+    fn test_update_by_pk() {
+        let updates = HashMap::from([("name", &"test_name" as &dyn ToSqlAny)]);
+        let query = EntityQueryBook::default().update_by_pk(updates, vec![&1_u32]);
+        assert_eq!(
+            query.to_string(),
+            "update some_schema.entity_table set name = $1 where id = $2 returning entity_table.id as id, entity_table.name as name, entity_table.score as score, entity_table.is_active as is_active"
+        );
+        assert_eq!(query.get_parameters().len(), 2);
+    }
+
+    #[test]
+    fn test_update_by_pk_with_expression() {
+        let updates = Updates::new().set_expression("score", "score + 1");
+        let query = EntityQueryBook::default().update_by_pk(updates, vec![&1_u32]);
+        assert!(query.to_string().starts_with(
+            "update some_schema.entity_table set score = score + 1 where id = $1 returning "
+        ));
+        assert_eq!(query.get_parameters().len(), 1);
+    }
+
+    #[test]
+    fn test_delete_by_pk() {
+        let query = EntityQueryBook::default().delete_by_pk(vec![&1_u32]);
+        assert_eq!(
+            query.to_string(),
+            "delete from some_schema.entity_table where id = $1 returning entity_table.id as id, entity_table.name as name, entity_table.score as score, entity_table.is_active as is_active"
+        );
+        assert_eq!(query.get_parameters().len(), 1);
+    }
+
+    #[test]
+    fn test_insert_many() {
+        let query_book = EntityQueryBook::default();
+        let queries = query_book.insert_many(vec![
+            HashMap::from([
+                ("name", &"first" as &dyn ToSqlAny),
+                ("score", &1_i32 as &dyn ToSqlAny),
+            ]),
+            HashMap::from([
+                ("name", &"second" as &dyn ToSqlAny),
+                ("is_active", &true as &dyn ToSqlAny),
+            ]),
+        ]);
+        assert_eq!(queries.len(), 1);
+        assert_eq!(
+            queries[0].to_string(),
+            "insert into some_schema.entity_table (name, score, is_active) values ($1, $2, default), ($3, default, $4) returning entity_table.id as id, entity_table.name as name, entity_table.score as score, entity_table.is_active as is_active"
+        );
+        let parameters = queries[0].get_parameters();
+        assert_eq!(parameters.len(), 4);
+        let parameter: &&str = (parameters[2] as &dyn Any).downcast_ref().unwrap();
+        assert_eq!(parameter, &"second");
+        let parameter: &bool = (parameters[3] as &dyn Any).downcast_ref().unwrap();
+        assert_eq!(parameter, &true);
+    }
+
+    #[test]
+    fn test_insert_many_chunks() {
+        let query_book = EntityQueryBook::default();
+        let row = HashMap::from([
+            ("id", &1_u32 as &dyn ToSqlAny),
+            ("name", &"name" as &dyn ToSqlAny),
+            ("score", &1_i32 as &dyn ToSqlAny),
+        ]);
+        let queries = query_book.insert_many(vec![row; MAX_QUERY_PARAMETERS / 3 + 1]);
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[0].get_parameters().len(), MAX_QUERY_PARAMETERS);
+        assert_eq!(queries[1].get_parameters().len(), 3);
+        assert_eq!(
+            queries[1].to_string(),
+            "insert into some_schema.entity_table (id, name, score) values ($1, $2, $3) returning entity_table.id as id, entity_table.name as name, entity_table.score as score, entity_table.is_active as is_active"
+        );
+
+        assert!(query_book.insert_many(Vec::new()).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_many_unknown_column() {
+        let _ = EntityQueryBook::default().insert_many(vec![HashMap::from([
+            ("name", &"name" as &dyn ToSqlAny),
+            ("unknown", &1_i32 as &dyn ToSqlAny),
+        ])]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_insert_many_no_column() {
+        let _ = EntityQueryBook::default().insert_many(vec![HashMap::new(), HashMap::new()]);
+    }
+
+    #[test]
+    fn test_upsert_do_nothing() {
+        let query = EntityQueryBook::default().upsert(
+            HashMap::from([
+                ("id", &1_u32 as &dyn ToSqlAny),
+                ("name", &"test_name" as &dyn ToSqlAny),
+            ]),
+            ConflictTarget::Columns(vec!["id"]),
+            ConflictAction::DoNothing,
+        );
+        assert_eq!(
+            query.to_string(),
+            "insert into some_schema.entity_table (id, name) values ($1, $2) on conflict (id) do nothing returning entity_table.id as id, entity_table.name as name, entity_table.score as score, entity_table.is_active as is_active"
+        );
+        assert_eq!(query.get_parameters().len(), 2);
+    }
+
+    #[test]
+    fn test_upsert_update_all() {
+        let query = EntityQueryBook::default().upsert(
+            HashMap::from([
+                ("id", &1_u32 as &dyn ToSqlAny),
+                ("name", &"test_name" as &dyn ToSqlAny),
+                ("score", &42_i32 as &dyn ToSqlAny),
+            ]),
+            ConflictTarget::Constraint("entity_table_pkey"),
+            ConflictAction::UpdateAll,
+        );
+        assert_eq!(
+            query.to_string(),
+            "insert into some_schema.entity_table (id, name, score) values ($1, $2, $3) on conflict on constraint entity_table_pkey do update set name = excluded.name, score = excluded.score returning entity_table.id as id, entity_table.name as name, entity_table.score as score, entity_table.is_active as is_active"
+        );
+        assert_eq!(query.get_parameters().len(), 3);
+    }
+
+    #[test]
+    fn test_upsert_update_columns() {
+        let query = EntityQueryBook::default().upsert(
+            HashMap::from([
+                ("id", &1_u32 as &dyn ToSqlAny),
+                ("name", &"test_name" as &dyn ToSqlAny),
+                ("score", &42_i32 as &dyn ToSqlAny),
+            ]),
+            ConflictTarget::Columns(vec!["name"]),
+            ConflictAction::Update {
+                columns: vec!["score"],
+                condition: Some(WhereCondition::new(
+                    "entity_table.score < $?",
+                    vec![&100_i32],
+                )),
+            },
+        );
+        assert_eq!(
+            query.to_string(),
+            "insert into some_schema.entity_table (id, name, score) values ($1, $2, $3) on conflict (name) do update set score = excluded.score where entity_table.score < $4 returning entity_table.id as id, entity_table.name as name, entity_table.score as score, entity_table.is_active as is_active"
+        );
+        let parameters = query.get_parameters();
+        assert_eq!(parameters.len(), 4);
+        let parameter: &i32 = (parameters[3] as &dyn Any).downcast_ref().unwrap();
+        assert_eq!(parameter, &100_i32);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_upsert_nothing_to_update() {
+        let _query = EntityQueryBook::default().upsert(
+            HashMap::from([("id", &1_u32 as &dyn ToSqlAny)]),
+            ConflictTarget::Columns(vec!["id"]),
+            ConflictAction::UpdateAll,
+        );
+    }
+
     #[test]
     fn test_insert() {
         let query = EntityQueryBook::default().insert(HashMap::from([
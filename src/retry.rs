@@ -0,0 +1,217 @@
+use std::time::Duration;
+
+use crate::{Error, Pool, Result, Transaction, TransactionOptions};
+
+/// How many times and how fast a transaction is replayed when it fails with a
+/// serialization failure or a deadlock. The delay between two attempts starts
+/// at the initial backoff and is multiplied after each failure up to the
+/// maximum backoff.
+///
+/// # Examples
+/// ```rust
+/// use std::time::Duration;
+/// use agrum::RetryPolicy;
+///
+/// let policy = RetryPolicy::new(5)
+///     .backoff(Duration::from_millis(20), Duration::from_millis(500))
+///     .multiplier(3);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    multiplier: u32,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_secs(1),
+            multiplier: 2,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a policy running the transaction at most `max_attempts` times
+    /// with the default backoff.
+    ///
+    /// It panics if `max_attempts` is zero.
+    pub fn new(max_attempts: u32) -> Self {
+        if max_attempts == 0 {
+            panic!("A transaction must be attempted at least once.");
+        }
+
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// Do not replay the transaction.
+    pub fn never() -> Self {
+        Self::new(1)
+    }
+
+    /// Set the delay before the first replay and the maximum delay between
+    /// two attempts.
+    pub fn backoff(mut self, initial_backoff: Duration, max_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Set the factor applied to the delay after each failed attempt.
+    pub fn multiplier(mut self, multiplier: u32) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Return the maximum number of attempts.
+    pub fn get_max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Return the delay to wait after the given failed attempt, starting at 1.
+    pub fn get_backoff(&self, attempt: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .checked_pow(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+
+        self.initial_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+}
+
+/// Run the given unit of work in a transaction started with the given options
+/// and commit it. When the transaction fails with a serialization failure or a
+/// deadlock (SQLSTATE 40001 or 40P01), it is rolled back and the whole unit of
+/// work is replayed in a new transaction according to the retry policy of the
+/// options. If it still fails after the last attempt, an
+/// [Error::RetriesExhausted] is returned. Other errors are returned as is
+/// without replay.
+///
+/// Since the closure may run several times, it must not have side effects
+/// outside of the transaction.
+///
+/// # Examples
+/// ```rust,no_run
+/// # use std::collections::HashMap;
+/// # use agrum::{
+/// #     InsertQueryBook, IsolationLevel, Pool, QueryBook, RetryPolicy, SqlEntity, Structured,
+/// #     ToSqlAny, TransactionOptions, run_in_transaction,
+/// # };
+/// # use futures_util::StreamExt;
+/// # #[derive(SqlEntity, Structured)]
+/// # struct Contact {
+/// #     contact_id: uuid::Uuid,
+/// #     name: String,
+/// # }
+/// # struct ContactQueryBook;
+/// # impl QueryBook<Contact> for ContactQueryBook {
+/// #     fn get_sql_source(&self) -> &'static str {
+/// #         "pommr.contact"
+/// #     }
+/// # }
+/// # impl InsertQueryBook<Contact> for ContactQueryBook {}
+/// # async fn example(
+/// #     pool: Pool,
+/// #     book: &ContactQueryBook,
+/// #     values: HashMap<&str, &dyn ToSqlAny>,
+/// # ) -> agrum::Result<()> {
+/// let options = TransactionOptions::new()
+///     .isolation_level(IsolationLevel::Serializable)
+///     .retry_policy(RetryPolicy::new(5));
+/// let contact = run_in_transaction(&pool, &options, async |transaction| {
+///     transaction.query(book.insert(values.clone())).await?.next().await.unwrap()
+/// })
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn run_in_transaction<R, F>(
+    pool: &Pool,
+    options: &TransactionOptions,
+    mut f: F,
+) -> Result<R>
+where
+    F: AsyncFnMut(&Transaction<'_>) -> Result<R>,
+{
+    let policy = options.get_retry_policy();
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+        let result = async {
+            let mut connection = pool.get().await?;
+            let transaction = connection
+                .build_transaction()
+                .options(options.clone())
+                .start()
+                .await?;
+
+            match f(&transaction).await {
+                Ok(result) => {
+                    transaction.commit().await?;
+                    Ok(result)
+                }
+                Err(error) => {
+                    transaction.rollback().await?;
+                    Err(error)
+                }
+            }
+        }
+        .await;
+
+        match result {
+            Err(error) if error.is_retryable() => {
+                if attempt >= policy.get_max_attempts() {
+                    return Err(Error::RetriesExhausted {
+                        attempts: attempt,
+                        last_error: Box::new(error),
+                    });
+                }
+
+                tokio::time::sleep(policy.get_backoff(attempt)).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_policy() {
+        let policy = RetryPolicy::default();
+
+        assert_eq!(3, policy.get_max_attempts());
+        assert_eq!(Duration::from_millis(10), policy.get_backoff(1));
+        assert_eq!(Duration::from_millis(20), policy.get_backoff(2));
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        let policy = RetryPolicy::new(100)
+            .backoff(Duration::from_millis(100), Duration::from_millis(250))
+            .multiplier(2);
+
+        assert_eq!(Duration::from_millis(100), policy.get_backoff(1));
+        assert_eq!(Duration::from_millis(200), policy.get_backoff(2));
+        assert_eq!(Duration::from_millis(250), policy.get_backoff(3));
+        assert_eq!(Duration::from_millis(250), policy.get_backoff(99));
+    }
+
+    #[test]
+    #[should_panic]
+    fn no_attempt() {
+        let _policy = RetryPolicy::new(0);
+    }
+}
@@ -0,0 +1,162 @@
+use std::{collections::HashMap, net::IpAddr, time::SystemTime};
+
+use chrono::{DateTime, FixedOffset, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use tokio_postgres::{Row, types::FromSql};
+use uuid::Uuid;
+
+use crate::{HydrationError, Projection, SqlEntity, Structure, Structured};
+
+/// A marker trait for the types that can be read from a single SQL value.
+/// The scalars and the tuples of scalars are [SqlEntity] so queries returning
+/// a count, an identifier or a couple of values do not need a dedicated entity.
+/// Their values are fetched by position and their structure is empty so their
+/// projection should not be used in the query templates.
+///
+/// # Examples
+/// ```rust
+/// use agrum::SqlQuery;
+/// use uuid::Uuid;
+///
+/// let count = SqlQuery::<i64>::new("select count(*) from pommr.contact");
+/// let names = SqlQuery::<(Uuid, String)>::new("select company_id, name from pommr.company");
+/// ```
+pub trait SqlScalar: for<'a> FromSql<'a> {}
+
+/// Fetch the value at the given position of the row.
+fn fetch<T: SqlScalar>(row: &Row, field_index: usize) -> Result<T, HydrationError> {
+    row.try_get(field_index)
+        .map_err(|error| HydrationError::FieldFetchFailed { error, field_index })
+}
+
+macro_rules! impl_sql_scalar {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl SqlScalar for $t {}
+            impl SqlScalar for Option<$t> {}
+
+            impl Structured for $t {
+                fn get_structure() -> Structure {
+                    Structure::default()
+                }
+            }
+
+            impl SqlEntity for $t {
+                fn get_projection() -> Projection<Self> {
+                    Projection::default()
+                }
+
+                fn hydrate(row: &Row) -> Result<Self, HydrationError> {
+                    fetch(row, 0)
+                }
+            }
+
+            impl Structured for Option<$t> {
+                fn get_structure() -> Structure {
+                    Structure::default()
+                }
+            }
+
+            impl SqlEntity for Option<$t> {
+                fn get_projection() -> Projection<Self> {
+                    Projection::default()
+                }
+
+                fn hydrate(row: &Row) -> Result<Self, HydrationError> {
+                    fetch(row, 0)
+                }
+            }
+        )*
+    };
+}
+
+impl_sql_scalar!(
+    bool,
+    i8,
+    i16,
+    i32,
+    i64,
+    u32,
+    f32,
+    f64,
+    String,
+    Vec<u8>,
+    Uuid,
+    IpAddr,
+    SystemTime,
+    NaiveDate,
+    NaiveTime,
+    NaiveDateTime,
+    DateTime<Utc>,
+    DateTime<Local>,
+    DateTime<FixedOffset>,
+    serde_json::Value,
+    HashMap<String, Option<String>>,
+    Vec<bool>,
+    Vec<i16>,
+    Vec<i32>,
+    Vec<i64>,
+    Vec<f32>,
+    Vec<f64>,
+    Vec<String>,
+    Vec<Uuid>,
+);
+
+macro_rules! impl_sql_tuple {
+    ($(($($t:ident : $index:tt),+)),* $(,)?) => {
+        $(
+            impl<$($t: SqlScalar),+> Structured for ($($t,)+) {
+                fn get_structure() -> Structure {
+                    Structure::default()
+                }
+            }
+
+            impl<$($t: SqlScalar),+> SqlEntity for ($($t,)+) {
+                fn get_projection() -> Projection<Self> {
+                    Projection::default()
+                }
+
+                fn hydrate(row: &Row) -> Result<Self, HydrationError> {
+                    Ok(($(fetch::<$t>(row, $index)?,)+))
+                }
+            }
+        )*
+    };
+}
+
+impl_sql_tuple!(
+    (A: 0, B: 1),
+    (A: 0, B: 1, C: 2),
+    (A: 0, B: 1, C: 2, D: 3),
+    (A: 0, B: 1, C: 2, D: 3, E: 4),
+    (A: 0, B: 1, C: 2, D: 3, E: 4, F: 5),
+    (A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6),
+    (A: 0, B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7),
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::SqlQuery;
+
+    use super::*;
+
+    #[test]
+    fn scalar_structure() {
+        assert!(i64::get_structure().get_fields().is_empty());
+        assert!(<Option<String>>::get_structure().get_fields().is_empty());
+        assert!(<(Uuid, String)>::get_structure().get_fields().is_empty());
+        assert_eq!("", i64::get_projection().to_string());
+    }
+
+    #[test]
+    fn scalar_query() {
+        let mut query = SqlQuery::<(Uuid, Option<String>)>::new(
+            "select contact_id, email from contact where name = $?",
+        );
+        query.add_parameter(&"whatever");
+
+        assert_eq!(
+            "select contact_id, email from contact where name = $1",
+            query.to_string()
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{Projection, SqlEntity, ToSqlAny};
+
+/// Direction of a sort.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Smallest values first.
+    Asc,
+
+    /// Greatest values first.
+    Desc,
+}
+
+impl SortOrder {
+    /// Return the SQL keyword of the direction.
+    pub fn as_sql(&self) -> &'static str {
+        match self {
+            Self::Asc => "asc",
+            Self::Desc => "desc",
+        }
+    }
+}
+
+/// The sort and the window of the rows returned by a select query, see
+/// [ReadQueryBook::select_with](crate::ReadQueryBook::select_with).
+/// The sorted fields must be fields of the projection. The limit and the offset
+/// are sent as parameters.
+///
+/// # Examples
+/// ```rust
+/// use agrum::{SelectOptions, SortOrder};
+///
+/// let page_size = 20_i64;
+/// let options = SelectOptions::new()
+///     .order_by("name", SortOrder::Asc)
+///     .order_by("company_id", SortOrder::Desc)
+///     .limit(&page_size)
+///     .offset(&40);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SelectOptions<'a> {
+    order_by: Vec<(String, SortOrder)>,
+    limit: Option<&'a i64>,
+    offset: Option<&'a i64>,
+}
+
+impl<'a> SelectOptions<'a> {
+    /// Create options that neither sort nor limit the rows.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sort the rows by the given projection field, after the fields already
+    /// given.
+    pub fn order_by(mut self, field: &str, order: SortOrder) -> Self {
+        self.order_by.push((field.to_string(), order));
+        self
+    }
+
+    /// Return at most the given number of rows.
+    pub fn limit(mut self, limit: &'a i64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skip the given number of rows.
+    pub fn offset(mut self, offset: &'a i64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Return the sorted fields in order.
+    pub fn get_order_by(&self) -> &[(String, SortOrder)] {
+        &self.order_by
+    }
+
+    /// Return the ` order by …` clause, empty if the rows are not sorted.
+    ///
+    /// It panics if a sorted field is not a field of the projection.
+    pub fn expand_order<T: SqlEntity>(&self, projection: &Projection<T>) -> String {
+        if self.order_by.is_empty() {
+            return String::new();
+        }
+
+        let fields = projection.get_fields();
+        let order_by: Vec<String> = self
+            .order_by
+            .iter()
+            .map(|(field, order)| {
+                if !fields.contains(field) {
+                    panic!(
+                        "Field {field} not found in projection. Available fields: '{}'.",
+                        fields.join(", ")
+                    );
+                }
+
+                format!("{field} {}", order.as_sql())
+            })
+            .collect();
+
+        format!(" order by {}", order_by.join(", "))
+    }
+
+    /// Return the ` limit $? offset $?` clause and its parameters, empty if
+    /// the rows are not limited.
+    pub fn expand_limit(&self) -> (String, Vec<&'a dyn ToSqlAny>) {
+        let mut clause = String::new();
+        let mut parameters: Vec<&'a dyn ToSqlAny> = Vec::new();
+
+        if let Some(limit) = self.limit {
+            clause.push_str(" limit $?");
+            parameters.push(limit);
+        }
+
+        if let Some(offset) = self.offset {
+            clause.push_str(" offset $?");
+            parameters.push(offset);
+        }
+
+        (clause, parameters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_postgres::Row;
+
+    use crate::{HydrationError, Structure, Structured};
+
+    use super::*;
+
+    struct TestEntity;
+
+    impl Structured for TestEntity {
+        fn get_structure() -> Structure {
+            Structure::new(&[("id", "int"), ("name", "text")])
+        }
+    }
+
+    impl SqlEntity for TestEntity {
+        fn get_projection() -> Projection<Self> {
+            Projection::default()
+        }
+
+        fn hydrate(_row: &Row) -> Result<Self, HydrationError> {
+            Ok(Self)
+        }
+    }
+
+    #[test]
+    fn test_default_options() {
+        let options = SelectOptions::new();
+
+        assert_eq!("", options.expand_order(&TestEntity::get_projection()));
+        assert_eq!("", options.expand_limit().0);
+    }
+
+    #[test]
+    fn test_options() {
+        let options = SelectOptions::new()
+            .order_by("name", SortOrder::Asc)
+            .order_by("id", SortOrder::Desc)
+            .limit(&10)
+            .offset(&20);
+
+        assert_eq!(
+            " order by name asc, id desc",
+            options.expand_order(&TestEntity::get_projection())
+        );
+        let (limit, parameters) = options.expand_limit();
+        assert_eq!(" limit $? offset $?", limit);
+        assert_eq!(2, parameters.len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unknown_field() {
+        let _ = SelectOptions::new()
+            .order_by("unknown", SortOrder::Asc)
+            .expand_order(&TestEntity::get_projection());
+    }
+}
@@ -30,9 +30,12 @@ impl StructureField {
     }
 }
 /// Structure of a SQL tuple.
+/// The structure may declare the fields that compose its primary key, it can
+/// be a composite key.
 #[derive(Debug, Clone, Default)]
 pub struct Structure {
     fields: Vec<StructureField>,
+    primary_key: Vec<String>,
 }
 
 impl Structure {
@@ -44,7 +47,26 @@ impl Structure {
             fields.push(StructureField::new(name, sql_type));
         }
 
-        Self { fields }
+        Self {
+            fields,
+            primary_key: Vec::new(),
+        }
+    }
+
+    /// Declare the fields composing the primary key, in key order. It panics
+    /// if a field is not declared in the structure.
+    pub fn with_primary_key(mut self, field_names: &[&str]) -> Self {
+        for name in field_names {
+            if !self.fields.iter().any(|f| f.name == *name) {
+                panic!(
+                    "Primary key field {name} not found in structure. Available fields: '{}'.",
+                    self.get_names().join(", ")
+                );
+            }
+        }
+        self.primary_key = field_names.iter().map(|name| name.to_string()).collect();
+
+        self
     }
 
     /// Set a field in the structure.
@@ -69,6 +91,12 @@ impl Structure {
 
         names
     }
+
+    /// Get the names of the fields composing the primary key. It is empty if
+    /// no primary key is declared.
+    pub fn get_primary_key(&self) -> Vec<&str> {
+        self.primary_key.iter().map(|name| name.as_str()).collect()
+    }
 }
 
 /// A trait to mark types that are structured.
@@ -156,4 +184,22 @@ mod tests {
         let structure = get_structure();
         assert_eq!(vec!["a_field", "another_field"], structure.get_names());
     }
+
+    #[test]
+    fn primary_key() {
+        let structure = get_structure();
+        assert!(structure.get_primary_key().is_empty());
+
+        let structure = get_structure().with_primary_key(&["another_field", "a_field"]);
+        assert_eq!(
+            vec!["another_field", "a_field"],
+            structure.get_primary_key()
+        );
+    }
+
+    #[test]
+    #[should_panic]
+    fn primary_key_unexistent_field() {
+        let _structure = get_structure().with_primary_key(&["a_field", "whatever"]);
+    }
 }
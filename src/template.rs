@@ -0,0 +1,273 @@
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    ops::Range,
+    sync::{LazyLock, PoisonError, RwLock},
+};
+
+/// A SQL template parsed once into literal, variable and parameter segments.
+/// Rendering a compiled template is a single linear pass, so it is worth
+/// keeping it in a `static` when the same template is used by many queries.
+/// The templates of the query books are compiled once by
+/// [CompiledTemplate::cached].
+///
+/// # Examples
+/// ```rust
+/// use std::sync::LazyLock;
+/// use agrum::{CompiledTemplate, SqlCommand, params};
+///
+/// static PURGE: LazyLock<CompiledTemplate> =
+///     LazyLock::new(|| CompiledTemplate::new("delete from {:source:} where created_at < $?"));
+///
+/// let mut command = SqlCommand::from_template(&PURGE);
+/// command
+///     .set_variable("source", "pommr.event")
+///     .set_parameters(params!["2024-01-01"]);
+///
+/// assert_eq!("delete from pommr.event where created_at < $1", command.to_string());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledTemplate {
+    template: String,
+    tokens: Vec<Token>,
+}
+
+impl CompiledTemplate {
+    /// Parse the given SQL template.
+    pub fn new(template: &str) -> Self {
+        Self {
+            tokens: tokenize(template, true),
+            template: template.to_string(),
+        }
+    }
+
+    /// Return the compiled version of the given static template. Each template
+    /// is compiled on first use and kept for the lifetime of the program.
+    pub fn cached(template: &'static str) -> &'static Self {
+        static CACHE: LazyLock<RwLock<HashMap<&'static str, &'static CompiledTemplate>>> =
+            LazyLock::new(RwLock::default);
+
+        if let Some(compiled) = CACHE
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(template)
+        {
+            return compiled;
+        }
+
+        CACHE
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(template)
+            .or_insert_with(|| Box::leak(Box::new(Self::new(template))))
+    }
+
+    /// Return the source of the template.
+    pub fn get_template(&self) -> &str {
+        &self.template
+    }
+
+    /// Return true if the template has a placeholder for the given variable.
+    pub fn has_variable(&self, name: &str) -> bool {
+        self.segments()
+            .any(|segment| segment == Segment::Variable(name))
+    }
+
+    /// Return the segments of the template in order.
+    pub(crate) fn segments(&self) -> impl Iterator<Item = Segment<'_>> {
+        self.tokens
+            .iter()
+            .map(|token| token.segment(&self.template))
+    }
+}
+
+/// Return the segments of a variable value. Variable placeholders are not
+/// expanded in values so they are kept as literals.
+pub(crate) fn value_segments(value: &str) -> impl Iterator<Item = Segment<'_>> {
+    tokenize(value, false)
+        .into_iter()
+        .map(move |token| token.segment(value))
+}
+
+/// Error raised when a SQL template is strictly expanded, see
+/// [SqlCommand::try_expand](crate::SqlCommand::try_expand).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A `{:variable:}` placeholder has no value.
+    UnresolvedVariable(String),
+
+    /// A variable is set but the template does not use it.
+    UnusedVariable(String),
+
+    /// A `$:name` placeholder has no value.
+    UnresolvedParameter(String),
+
+    /// A named parameter is set but the template does not use it.
+    UnusedParameter(String),
+
+    /// The number of `$?` placeholders differs from the number of positional
+    /// parameters.
+    ParameterCount {
+        /// Number of `$?` placeholders, including those of the variables.
+        placeholders: usize,
+        /// Number of positional parameters.
+        parameters: usize,
+    },
+}
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnresolvedVariable(name) => write!(f, "variable '{name}' has no value"),
+            Self::UnusedVariable(name) => write!(f, "variable '{name}' is not used"),
+            Self::UnresolvedParameter(name) => write!(f, "parameter '{name}' has no value"),
+            Self::UnusedParameter(name) => write!(f, "parameter '{name}' is not used"),
+            Self::ParameterCount {
+                placeholders,
+                parameters,
+            } => write!(
+                f,
+                "{placeholders} parameter placeholders for {parameters} parameters"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// A part of a SQL template.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Segment<'t> {
+    /// SQL text sent as is.
+    Literal(&'t str),
+
+    /// A `{:name:}` variable placeholder.
+    Variable(&'t str),
+
+    /// A `$?` positional parameter placeholder.
+    Parameter,
+
+    /// A `$:name` named parameter placeholder.
+    NamedParameter(&'t str),
+}
+
+/// A segment stored as positions in the template.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Literal(Range<usize>),
+    Variable(Range<usize>),
+    Parameter,
+    NamedParameter(Range<usize>),
+}
+
+impl Token {
+    fn segment<'t>(&self, template: &'t str) -> Segment<'t> {
+        match self {
+            Self::Literal(range) => Segment::Literal(&template[range.clone()]),
+            Self::Variable(range) => Segment::Variable(&template[range.clone()]),
+            Self::Parameter => Segment::Parameter,
+            Self::NamedParameter(range) => Segment::NamedParameter(&template[range.clone()]),
+        }
+    }
+}
+
+/// Split the template in tokens in a single pass. When `variables` is false,
+/// the variable placeholders are kept as literals.
+fn tokenize(template: &str, variables: bool) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut literal_start = 0;
+    let mut position = 0;
+
+    while let Some(offset) = template[position..].find(['$', '{']) {
+        let start = position + offset;
+        let rest = &template[start..];
+        let found = if rest.starts_with("$?") {
+            Some((Token::Parameter, 2))
+        } else if let Some(tail) = rest.strip_prefix("$:") {
+            let length = identifier_length(tail);
+            (length > 0).then(|| {
+                (
+                    Token::NamedParameter(start + 2..start + 2 + length),
+                    length + 2,
+                )
+            })
+        } else if let Some(tail) = rest.strip_prefix("{:").filter(|_| variables) {
+            let length = identifier_length(tail);
+            (length > 0 && tail[length..].starts_with(":}"))
+                .then(|| (Token::Variable(start + 2..start + 2 + length), length + 4))
+        } else {
+            None
+        };
+
+        match found {
+            Some((token, length)) => {
+                if literal_start < start {
+                    tokens.push(Token::Literal(literal_start..start));
+                }
+                tokens.push(token);
+                position = start + length;
+                literal_start = position;
+            }
+            None => position = start + 1,
+        }
+    }
+
+    if literal_start < template.len() {
+        tokens.push(Token::Literal(literal_start..template.len()));
+    }
+
+    tokens
+}
+
+/// Return the length of the identifier at the beginning of the text.
+fn identifier_length(text: &str) -> usize {
+    text.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments() {
+        let template = CompiledTemplate::new(
+            "select {:projection:} from {:bad name:} where a = $? and b = $:b_1::text",
+        );
+
+        assert_eq!(
+            vec![
+                Segment::Literal("select "),
+                Segment::Variable("projection"),
+                Segment::Literal(" from {:bad name:} where a = "),
+                Segment::Parameter,
+                Segment::Literal(" and b = "),
+                Segment::NamedParameter("b_1"),
+                Segment::Literal("::text"),
+            ],
+            template.segments().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_value_segments() {
+        assert_eq!(
+            vec![Segment::Literal("{:a:} = "), Segment::Parameter],
+            value_segments("{:a:} = $?").collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_cached() {
+        let first = CompiledTemplate::cached("select {:projection:} from {:source:}");
+        let second = CompiledTemplate::cached("select {:projection:} from {:source:}");
+
+        assert!(std::ptr::eq(first, second));
+        assert!(second.has_variable("source"));
+        assert!(!second.has_variable("condition"));
+        assert_eq!(
+            "select {:projection:} from {:source:}",
+            second.get_template()
+        );
+    }
+}
@@ -0,0 +1,236 @@
+use tokio_postgres::{Client, IsolationLevel as TokioIsolationLevel};
+
+use crate::{Result, RetryPolicy, Transaction};
+
+/// Isolation level of a transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IsolationLevel {
+    /// Each statement sees the rows committed before it began. This is the
+    /// PostgreSQL default.
+    ReadCommitted,
+
+    /// All the statements see the rows committed before the first statement of
+    /// the transaction.
+    RepeatableRead,
+
+    /// The transaction behaves as if the serializable transactions were run one
+    /// after the other. It may fail with a serialization error and must then be
+    /// retried.
+    Serializable,
+}
+
+impl From<IsolationLevel> for TokioIsolationLevel {
+    fn from(level: IsolationLevel) -> Self {
+        match level {
+            IsolationLevel::ReadCommitted => Self::ReadCommitted,
+            IsolationLevel::RepeatableRead => Self::RepeatableRead,
+            IsolationLevel::Serializable => Self::Serializable,
+        }
+    }
+}
+
+/// The consistency requirements of a transaction.
+/// Unset options fall back to the server defaults. The retry policy is only
+/// used by [run_in_transaction](crate::run_in_transaction).
+///
+/// # Examples
+/// ```rust
+/// use agrum::{IsolationLevel, TransactionOptions};
+///
+/// let options = TransactionOptions::new()
+///     .isolation_level(IsolationLevel::Serializable)
+///     .read_only(true)
+///     .set_local("statement_timeout", "5s");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionOptions {
+    isolation_level: Option<IsolationLevel>,
+    read_only: Option<bool>,
+    deferrable: Option<bool>,
+    settings: Vec<(String, String)>,
+    retry_policy: RetryPolicy,
+}
+
+impl TransactionOptions {
+    /// Create options with the server defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the isolation level of the transaction.
+    pub fn isolation_level(mut self, isolation_level: IsolationLevel) -> Self {
+        self.isolation_level = Some(isolation_level);
+        self
+    }
+
+    /// Set the access mode of the transaction.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = Some(read_only);
+        self
+    }
+
+    /// Set the deferrability of the transaction. It only has effect on
+    /// serializable read only transactions.
+    pub fn deferrable(mut self, deferrable: bool) -> Self {
+        self.deferrable = Some(deferrable);
+        self
+    }
+
+    /// Set a configuration parameter for the duration of the transaction, as
+    /// `SET LOCAL` does.
+    pub fn set_local(mut self, name: &str, value: &str) -> Self {
+        self.settings.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Set how the transaction is replayed on serialization failures and
+    /// deadlocks.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Return the isolation level if set.
+    pub fn get_isolation_level(&self) -> Option<IsolationLevel> {
+        self.isolation_level
+    }
+
+    /// Return the local settings in declaration order.
+    pub fn get_settings(&self) -> &[(String, String)] {
+        &self.settings
+    }
+
+    /// Return the retry policy.
+    pub fn get_retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+}
+
+/// A builder that starts a [Transaction] from a database connection.
+/// It is created by [Transaction::build].
+pub struct TransactionBuilder<'a> {
+    client: &'a mut Client,
+    options: TransactionOptions,
+}
+
+impl<'a> TransactionBuilder<'a> {
+    /// Replace the options of the transaction.
+    pub fn options(mut self, options: TransactionOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Set the isolation level of the transaction.
+    pub fn isolation_level(mut self, isolation_level: IsolationLevel) -> Self {
+        self.options = self.options.isolation_level(isolation_level);
+        self
+    }
+
+    /// Set the access mode of the transaction.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.options = self.options.read_only(read_only);
+        self
+    }
+
+    /// Set the deferrability of the transaction.
+    pub fn deferrable(mut self, deferrable: bool) -> Self {
+        self.options = self.options.deferrable(deferrable);
+        self
+    }
+
+    /// Set a configuration parameter for the duration of the transaction.
+    pub fn set_local(mut self, name: &str, value: &str) -> Self {
+        self.options = self.options.set_local(name, value);
+        self
+    }
+
+    /// Start the transaction and apply the local settings.
+    pub async fn start(self) -> Result<Transaction<'a>> {
+        let options = self.options;
+        let mut builder = self.client.build_transaction();
+
+        if let Some(isolation_level) = options.isolation_level {
+            builder = builder.isolation_level(isolation_level.into());
+        }
+
+        if let Some(read_only) = options.read_only {
+            builder = builder.read_only(read_only);
+        }
+
+        if let Some(deferrable) = options.deferrable {
+            builder = builder.deferrable(deferrable);
+        }
+
+        let transaction = builder.start().await?;
+
+        for (name, value) in &options.settings {
+            transaction
+                .execute("select set_config($1, $2, true)", &[name, value])
+                .await?;
+        }
+
+        Ok(Transaction::start(transaction).await)
+    }
+}
+
+impl<'a> Transaction<'a> {
+    /// Create a builder to start a transaction with the given options on the
+    /// connection. Pooled connections dereference to the client so they can be
+    /// given directly.
+    ///
+    /// # Examples
+    /// ```rust,no_run
+    /// # use agrum::{IsolationLevel, Pool, Transaction};
+    /// # async fn example(pool: Pool) -> agrum::Result<()> {
+    /// let mut connection = pool.get().await?;
+    /// let transaction = Transaction::build(&mut connection)
+    ///     .isolation_level(IsolationLevel::RepeatableRead)
+    ///     .read_only(true)
+    ///     .start()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn build(client: &'a mut Client) -> TransactionBuilder<'a> {
+        TransactionBuilder {
+            client,
+            options: TransactionOptions::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_options() {
+        let options = TransactionOptions::new();
+
+        assert_eq!(None, options.get_isolation_level());
+        assert!(options.get_settings().is_empty());
+    }
+
+    #[test]
+    fn set_options() {
+        let options = TransactionOptions::new()
+            .isolation_level(IsolationLevel::RepeatableRead)
+            .isolation_level(IsolationLevel::Serializable)
+            .read_only(true)
+            .deferrable(true)
+            .set_local("statement_timeout", "5s")
+            .set_local("search_path", "pommr");
+
+        assert_eq!(
+            Some(IsolationLevel::Serializable),
+            options.get_isolation_level()
+        );
+        assert_eq!(
+            vec![
+                ("statement_timeout".to_string(), "5s".to_string()),
+                ("search_path".to_string(), "pommr".to_string()),
+            ],
+            options.get_settings()
+        );
+    }
+}
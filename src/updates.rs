@@ -0,0 +1,167 @@
+use std::collections::HashMap;
+
+use crate::{Structure, ToSqlAny};
+
+/// The `set` clause of an update query.
+/// The columns are set either to a value sent as a parameter or to a SQL
+/// expression that may hold its own `$?` parameters. Whatever the order the
+/// columns are given, they are expanded in the order of the fields of the
+/// structure so the generated SQL is always the same.
+///
+/// # Examples
+/// ```rust
+/// use agrum::{Structure, Updates, params};
+///
+/// let structure = Structure::new(&[
+///     ("counter_id", "int"),
+///     ("name", "text"),
+///     ("counter", "int"),
+///     ("updated_at", "timestamptz"),
+/// ]);
+/// let (updates, parameters) = Updates::new()
+///     .set_expression("updated_at", "now()")
+///     .set_expression_with("counter", "counter + $?", params![1_i32])
+///     .set("name", &"visits")
+///     .expand(&structure);
+///
+/// assert_eq!("name = $?, counter = counter + $?, updated_at = now()", updates);
+/// assert_eq!(2, parameters.len());
+/// ```
+#[derive(Default)]
+pub struct Updates<'a> {
+    updates: HashMap<&'a str, (String, Vec<&'a dyn ToSqlAny>)>,
+}
+
+impl<'a> Updates<'a> {
+    /// Create an empty set of updates.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the column to the given value. A column set twice keeps the last
+    /// update.
+    pub fn set(self, column: &'a str, value: &'a dyn ToSqlAny) -> Self {
+        self.set_expression_with(column, "$?", vec![value])
+    }
+
+    /// Set the column to the given SQL expression like `now()`.
+    pub fn set_expression(self, column: &'a str, expression: &str) -> Self {
+        self.set_expression_with(column, expression, Vec::new())
+    }
+
+    /// Set the column to the given SQL expression holding `$?` parameters like
+    /// `counter + $?`.
+    pub fn set_expression_with(
+        mut self,
+        column: &'a str,
+        expression: &str,
+        parameters: Vec<&'a dyn ToSqlAny>,
+    ) -> Self {
+        self.updates
+            .insert(column, (expression.to_string(), parameters));
+        self
+    }
+
+    /// Return true if no column is updated.
+    pub fn is_empty(&self) -> bool {
+        self.updates.is_empty()
+    }
+
+    /// Return the `set` clause and its parameters, the columns being in the
+    /// structure order.
+    ///
+    /// It panics if a column is not declared in the structure or if there is
+    /// no column to update.
+    pub fn expand(self, structure: &Structure) -> (String, Vec<&'a dyn ToSqlAny>) {
+        let names = structure.get_names();
+
+        if self.updates.is_empty() {
+            panic!("No column to update.");
+        }
+
+        for column in self.updates.keys() {
+            if !names.contains(column) {
+                panic!(
+                    "Field {column} not found in structure. Available fields: '{}'.",
+                    names.join(", ")
+                );
+            }
+        }
+
+        let mut updates = self.updates;
+        let mut fragments = Vec::with_capacity(updates.len());
+        let mut parameters = Vec::new();
+
+        for name in names {
+            if let Some((expression, expression_parameters)) = updates.remove(name) {
+                fragments.push(format!("{name} = {expression}"));
+                parameters.extend(expression_parameters);
+            }
+        }
+
+        (fragments.join(", "), parameters)
+    }
+}
+
+impl<'a> From<HashMap<&'a str, &'a dyn ToSqlAny>> for Updates<'a> {
+    fn from(values: HashMap<&'a str, &'a dyn ToSqlAny>) -> Self {
+        values
+            .into_iter()
+            .fold(Self::new(), |updates, (column, value)| {
+                updates.set(column, value)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use crate::params;
+
+    use super::*;
+
+    fn structure() -> Structure {
+        Structure::new(&[("id", "int"), ("name", "text"), ("score", "int")])
+    }
+
+    #[test]
+    fn test_structure_order() {
+        let (updates, parameters) = Updates::from(HashMap::from([
+            ("score", &2_i32 as &dyn ToSqlAny),
+            ("name", &"name"),
+            ("id", &1_i32),
+        ]))
+        .expand(&structure());
+
+        assert_eq!("id = $?, name = $?, score = $?", updates);
+        let parameters: Vec<&dyn Any> = parameters.into_iter().map(|p| p as &dyn Any).collect();
+        assert_eq!(Some(&1_i32), parameters[0].downcast_ref());
+        assert_eq!(Some(&"name"), parameters[1].downcast_ref());
+        assert_eq!(Some(&2_i32), parameters[2].downcast_ref());
+    }
+
+    #[test]
+    fn test_expressions() {
+        let (updates, parameters) = Updates::new()
+            .set("score", &1_i32)
+            .set_expression_with("score", "score + $? * $?", params![2_i32, 3_i32])
+            .set_expression("name", "upper(name)")
+            .expand(&structure());
+
+        assert_eq!("name = upper(name), score = score + $? * $?", updates);
+        assert_eq!(2, parameters.len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_unknown_column() {
+        let _ = Updates::new().set("unknown", &1_i32).expand(&structure());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_no_update() {
+        let _ = Updates::new().expand(&structure());
+    }
+}
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use crate::{CompiledTemplate, ConstraintErrorMap, SqlCommand, SqlEntity, SqlQuery, ToSqlAny};
+
+/// A composer of common table expressions.
+/// The named queries are rendered as `with name as (…), …` before the final
+/// query, each of them keeping its own parameters so the parameters of the
+/// resulting query are in the order of their placeholders. Any query can be
+/// used as a common table expression, including the data modifying queries of
+/// the query books like `insert … returning`.
+///
+/// # Examples
+/// ```rust,no_run
+/// # use std::collections::HashMap;
+/// # use agrum::{
+/// #     InsertQueryBook, QueryBook, SqlEntity, SqlQuery, Structured, ToSqlAny, Transaction,
+/// #     WithQuery,
+/// # };
+/// # #[derive(SqlEntity, Structured)]
+/// # struct Company {
+/// #     company_id: uuid::Uuid,
+/// #     name: String,
+/// # }
+/// # struct CompanyQueryBook;
+/// # impl QueryBook<Company> for CompanyQueryBook {
+/// #     fn get_sql_source(&self) -> &'static str {
+/// #         "pommr.company"
+/// #     }
+/// # }
+/// # impl InsertQueryBook<Company> for CompanyQueryBook {}
+/// # async fn example(
+/// #     transaction: &Transaction<'_>,
+/// #     company_book: &CompanyQueryBook,
+/// #     values: HashMap<&str, &dyn ToSqlAny>,
+/// # ) -> agrum::Result<()> {
+/// let new_company = company_book.insert(values);
+/// let query = SqlQuery::<Company>::new("select {:projection:} from new_company");
+/// let query = WithQuery::new()
+///     .with("new_company", new_company)
+///     .select(query);
+/// let company = transaction.query_one(query).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct WithQuery<'a> {
+    recursive: bool,
+    expressions: Vec<(&'a str, String, Vec<&'a dyn ToSqlAny>)>,
+    constraint_error_map: Option<Arc<ConstraintErrorMap>>,
+}
+
+impl<'a> WithQuery<'a> {
+    /// Create a composer without common table expressions.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the common table expressions recursive, they may refer to
+    /// themselves.
+    pub fn recursive(mut self) -> Self {
+        self.recursive = true;
+        self
+    }
+
+    /// Add a common table expression. Its name may declare the column names
+    /// like `contact_value (name, email)`. Expressions can refer to the
+    /// expressions added before them.
+    /// The constraint error map of the first query that has one is used for
+    /// the resulting query unless the final query has one.
+    pub fn with(mut self, name: &'a str, query: impl Into<SqlCommand<'a>>) -> Self {
+        let query = query.into();
+
+        if self.constraint_error_map.is_none() {
+            self.constraint_error_map = query.get_constraint_error_map().cloned();
+        }
+
+        let (query, parameters) = query.expand_positional();
+        self.expressions.push((name, query, parameters));
+        self
+    }
+
+    /// Return the final query preceded by the common table expressions.
+    pub fn select<T: SqlEntity>(self, query: SqlQuery<'a, T>) -> SqlQuery<'a, T> {
+        if self.expressions.is_empty() {
+            return query;
+        }
+
+        let constraint_error_map = query
+            .get_constraint_error_map()
+            .cloned()
+            .or(self.constraint_error_map);
+        let mut expressions = Vec::with_capacity(self.expressions.len());
+        let mut parameters = Vec::new();
+
+        for (name, expression, expression_parameters) in self.expressions {
+            expressions.push(format!("{name} as ({expression})"));
+            parameters.extend(expression_parameters);
+        }
+
+        let mut with_query = SqlQuery::from_template(CompiledTemplate::cached(
+            "with {:recursive:}{:expressions:} {:query:}",
+        ));
+        with_query
+            .set_variable("recursive", if self.recursive { "recursive " } else { "" })
+            .set_fragment("expressions", &expressions.join(", "), parameters)
+            .set_subquery("query", query)
+            .set_constraint_error_map(constraint_error_map);
+
+        with_query
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::any::Any;
+
+    use tokio_postgres::Row;
+
+    use crate::{HydrationError, Projection, Structure, Structured, params};
+
+    use super::*;
+
+    struct TestEntity;
+
+    impl Structured for TestEntity {
+        fn get_structure() -> Structure {
+            Structure::new(&[("id", "int")])
+        }
+    }
+
+    impl SqlEntity for TestEntity {
+        fn get_projection() -> Projection<Self> {
+            Projection::default()
+        }
+
+        fn hydrate(_row: &Row) -> Result<Self, HydrationError> {
+            Ok(Self)
+        }
+    }
+
+    #[test]
+    fn test_with_query() {
+        let mut inserted = SqlQuery::<TestEntity>::new(
+            "insert into some_table (id, name) values ($?, $:name) returning {:projection:}",
+        );
+        inserted
+            .add_parameter(&1_i32)
+            .set_named_parameter("name", &"one");
+        let mut values = SqlCommand::new("values ($?)");
+        values.add_parameter(&2_i32);
+        let mut query = SqlQuery::<TestEntity>::new(
+            "select {:projection:} from inserted join value using (id) where id > $?",
+        );
+        query.add_parameter(&0_i32);
+
+        let query = WithQuery::new()
+            .with("inserted", inserted)
+            .with("value (id)", values)
+            .select(query);
+        let (sql, parameters) = query.try_expand().unwrap();
+
+        assert_eq!(
+            "with inserted as (insert into some_table (id, name) values ($1, $2) returning id as id), value (id) as (values ($3)) select id as id from inserted join value using (id) where id > $4",
+            sql
+        );
+        let first: &i32 = (parameters[0] as &dyn Any).downcast_ref().unwrap();
+        assert_eq!(first, &1_i32);
+        let last: &i32 = (parameters[3] as &dyn Any).downcast_ref().unwrap();
+        assert_eq!(last, &0_i32);
+    }
+
+    #[test]
+    fn test_recursive() {
+        let mut tree = SqlCommand::new(
+            "select id from node where id = $? union all select node.id from node join tree on node.parent_id = tree.id",
+        );
+        tree.set_parameters(params![1_i32]);
+        let query =
+            WithQuery::new()
+                .recursive()
+                .with("tree", tree)
+                .select(SqlQuery::<TestEntity>::new(
+                    "select {:projection:} from tree",
+                ));
+
+        assert!(
+            query
+                .to_string()
+                .starts_with("with recursive tree as (select id from node where id = $1 union")
+        );
+    }
+
+    #[test]
+    fn test_without_expressions() {
+        let query = WithQuery::new().select(SqlQuery::<TestEntity>::new("select 1"));
+
+        assert_eq!("select 1", query.to_string());
+    }
+}
@@ -1,8 +1,6 @@
 use std::marker::PhantomData;
 
-use agrum::{
-    Projection, QueryBook, SqlEntity, SqlQuery, Structure, Structured, Transaction, WhereCondition,
-};
+use agrum::{Projection, QueryBook, SqlEntity, SqlQuery, Structure, Structured, WhereCondition};
 use futures_util::stream::StreamExt;
 use uuid::Uuid;
 
@@ -112,7 +110,7 @@ where {:condition:}"#
 async fn test_address_aggregate_query_book() {
     let pool = get_pool().await;
     let mut connection = pool.get().await.unwrap();
-    let transaction = Transaction::start(connection.transaction().await.unwrap()).await;
+    let transaction = connection.transaction().await.unwrap();
 
     let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
     let query = AddressAggregateQueryBook::<AddressAggregateEntity>::default().select(
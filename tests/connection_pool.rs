@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use agrum::{Error, Pool, SqlQuery, Transaction, TransactionOptions};
+
+fn get_dsn() -> String {
+    let _ = dotenvy::dotenv();
+
+    std::env::var("PG_DSN")
+        .ok()
+        .filter(|s| !s.is_empty())
+        .expect("PG_DSN is not set (set it in the environment or in a .env file)")
+}
+
+async fn current_setting(transaction: &Transaction<'_>, name: &'static str) -> String {
+    let mut query = SqlQuery::<String>::new("select current_setting($?)");
+    query.add_parameter(&name);
+
+    transaction.query_one(query).await.unwrap()
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_connection_initialization() {
+    let pool = Pool::builder(&get_dsn())
+        .search_path(&["pommr", "public"])
+        .application_name("agrum-pool-test")
+        .on_connect("set timezone to 'Europe/Paris'")
+        .build()
+        .await
+        .unwrap();
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+
+    assert_eq!(
+        current_setting(&transaction, "search_path").await,
+        r#""pommr", "public""#
+    );
+    assert_eq!(
+        current_setting(&transaction, "application_name").await,
+        "agrum-pool-test"
+    );
+    assert_eq!(
+        current_setting(&transaction, "timezone").await,
+        "Europe/Paris"
+    );
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_pool_transaction_and_statistics() {
+    let pool = Pool::builder(&get_dsn()).max_size(2).build().await.unwrap();
+
+    let value = pool
+        .transaction(&TransactionOptions::new(), async |transaction| {
+            Ok(current_setting(transaction, "transaction_isolation").await)
+        })
+        .await
+        .unwrap();
+    assert_eq!(value, "read committed");
+
+    let statistics = pool.statistics();
+    assert!(statistics.connections >= 1);
+    assert!(statistics.gets >= 1);
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_pool_timeout() {
+    let pool = Pool::builder(&get_dsn())
+        .max_size(1)
+        .connection_timeout(Duration::from_millis(100))
+        .build()
+        .await
+        .unwrap();
+    let _connection = pool.get().await.unwrap();
+
+    let error = pool.get().await.map(|_| ()).unwrap_err();
+    assert!(matches!(error, Error::PoolTimeout));
+    assert_eq!(pool.statistics().gets_timed_out, 1);
+}
@@ -0,0 +1,216 @@
+use futures_util::stream::{self, StreamExt};
+use uuid::Uuid;
+
+use agrum::{CopyFormat, Error, ReadQueryBook, ToSqlAny, WhereCondition, params};
+
+mod model;
+use model::*;
+
+mod pool;
+use pool::get_pool;
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_copy_in_entities() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+
+    let company_id = Uuid::parse_str(COMPANY_2_ID).unwrap();
+    let contacts = (0..1000).map(|index| Contact {
+        contact_id: Uuid::new_v4(),
+        name: format!("contact {index}"),
+        email: (index % 2 == 0).then(|| format!("contact{index}@email.com")),
+        phone_number: None,
+        company_id,
+    });
+    let book = ContactQueryBook::<Contact>::default();
+    let copied = transaction
+        .copy_in(&book, stream::iter(contacts))
+        .await
+        .unwrap();
+    assert_eq!(copied, 1000);
+
+    let contacts = transaction
+        .query(book.select(WhereCondition::new("company_id = $?", params![company_id])))
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(contacts.len(), 1001);
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_copy_in_values() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+
+    let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
+    let contact_id = Uuid::new_v4();
+    let no_value: Option<String> = None;
+    let rows: Vec<Vec<&dyn ToSqlAny>> = vec![vec![
+        &contact_id,
+        &"copied contact",
+        &no_value,
+        &no_value,
+        &company_id,
+    ]];
+    let book = ContactQueryBook::<Contact>::default();
+    let copied = transaction
+        .copy_in(&book, stream::iter(rows))
+        .await
+        .unwrap();
+    assert_eq!(copied, 1);
+
+    let contact = transaction
+        .query(book.select(WhereCondition::new("contact_id = $?", params![contact_id])))
+        .await
+        .unwrap()
+        .next()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(contact.name, "copied contact");
+    assert_eq!(contact.email, None);
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_copy_in_invalid_rows() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+    let book = ContactQueryBook::<Contact>::default();
+
+    let contact_id = Uuid::new_v4();
+    let rows: Vec<Vec<&dyn ToSqlAny>> = vec![vec![&contact_id, &"incomplete contact"]];
+    let error = transaction
+        .copy_in(&book, stream::iter(rows))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::Copy(_)));
+    transaction.rollback().await.unwrap();
+
+    let transaction = connection.transaction().await.unwrap();
+    let unknown_company_id = Uuid::new_v4();
+    let no_value: Option<String> = None;
+    let rows: Vec<Vec<&dyn ToSqlAny>> = vec![vec![
+        &contact_id,
+        &"orphan contact",
+        &no_value,
+        &no_value,
+        &unknown_company_id,
+    ]];
+    let error = transaction
+        .copy_in(&book, stream::iter(rows))
+        .await
+        .unwrap_err();
+    let Error::Domain(error) = error else {
+        panic!("expected a domain error, got «{error}»");
+    };
+    assert_eq!(
+        Some(&ContactError::UnknownCompany),
+        error.downcast_ref::<ContactError>()
+    );
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_copy_out_entities() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+
+    let query = ContactQueryBook::<Contact>::default().select(WhereCondition::default());
+    let contacts = transaction
+        .copy_out(query)
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(contacts.len(), 2);
+    let contact = contacts
+        .iter()
+        .map(|contact| contact.as_ref().unwrap())
+        .find(|contact| contact.contact_id == Uuid::parse_str(CONTACT_1_ID).unwrap())
+        .unwrap();
+    assert_eq!(contact.company_id, Uuid::parse_str(COMPANY_1_ID).unwrap());
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_copy_out_csv() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+
+    let query = CompanyQueryBook::<Company>::default().select(WhereCondition::default());
+    let chunks = transaction
+        .copy_out_raw(query, CopyFormat::Csv { header: true })
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    let csv: String = chunks
+        .into_iter()
+        .map(|chunk| String::from_utf8(chunk.unwrap().to_vec()).unwrap())
+        .collect();
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("company_id,name,default_address_id"));
+    assert_eq!(lines.count(), 2);
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_copy_out_with_parameters() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+
+    let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
+    let query = CompanyQueryBook::<Company>::default()
+        .select(WhereCondition::new("company_id = $?", params![company_id]));
+    let companies = transaction
+        .copy_out(query)
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    assert_eq!(companies.len(), 1);
+    let company = companies[0].as_ref().unwrap();
+    assert_eq!(company.company_id, company_id);
+    assert_eq!(company.name, "first");
+
+    // Quotes, nulls and arrays are turned into literals by the server.
+    let names = vec!["first", "it's"];
+    let query = ContactQueryBook::<Contact>::default().select(
+        WhereCondition::new(
+            "company_id in (select company_id from pommr.company where name = any($?))",
+            params![names],
+        )
+        .and_where(WhereCondition::new(
+            "phone_number is not distinct from $?",
+            params![None::<String>],
+        )),
+    );
+    let chunks = transaction
+        .copy_out_raw(query, CopyFormat::Csv { header: false })
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await;
+    let csv: String = chunks
+        .into_iter()
+        .map(|chunk| String::from_utf8(chunk.unwrap().to_vec()).unwrap())
+        .collect();
+    assert_eq!(csv.lines().count(), 1);
+    assert!(csv.starts_with(CONTACT_1_ID));
+    transaction.rollback().await.unwrap();
+}
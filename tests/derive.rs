@@ -0,0 +1,63 @@
+use agrum::{SqlEntity, Structured};
+use uuid::Uuid;
+
+mod model;
+use model::*;
+
+#[derive(SqlEntity, Structured)]
+#[agrum(alias = "company")]
+pub struct CompanyShort {
+    pub company_id: Uuid,
+    #[agrum(rename = "name")]
+    pub company_name: String,
+    #[agrum(sql_type = "bigint", projection = "count(contact.company_id)")]
+    pub contacts_nb: i64,
+    pub tags: Vec<String>,
+}
+
+#[test]
+fn derive_structure() {
+    let structure = Contact::get_structure();
+    let fields: Vec<(&str, &str)> = structure.get_fields().iter().map(|f| f.dump()).collect();
+
+    assert_eq!(
+        vec![
+            ("contact_id", "uuid"),
+            ("name", "text"),
+            ("email", "text"),
+            ("phone_number", "text"),
+            ("company_id", "uuid"),
+        ],
+        fields
+    );
+    assert_eq!(vec!["contact_id"], structure.get_primary_key());
+}
+
+#[test]
+fn derive_default_projection() {
+    assert_eq!(
+        "address_id as address_id, label as label, company_id as company_id, content as content, zipcode as zipcode, city as city, associated_contact_id as associated_contact_id",
+        Address::get_projection().to_string()
+    );
+}
+
+#[test]
+fn derive_with_attributes() {
+    let structure = CompanyShort::get_structure();
+    let fields: Vec<(&str, &str)> = structure.get_fields().iter().map(|f| f.dump()).collect();
+
+    assert_eq!(
+        vec![
+            ("company_id", "uuid"),
+            ("name", "text"),
+            ("contacts_nb", "bigint"),
+            ("tags", "text[]"),
+        ],
+        fields
+    );
+    assert!(structure.get_primary_key().is_empty());
+    assert_eq!(
+        "company.company_id as company_id, company.name as name, count(contact.company_id) as contacts_nb, company.tags as tags",
+        CompanyShort::get_projection().to_string()
+    );
+}
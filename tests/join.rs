@@ -1,7 +1,7 @@
 use std::{any::Any, marker::PhantomData};
 
 use agrum::{
-    Projection, QueryBook, ReadQueryBook, SqlEntity, SqlQuery, Structure, Structured, Transaction,
+    Projection, QueryBook, ReadQueryBook, SqlEntity, SqlQuery, Structure, Structured,
     WhereCondition,
 };
 use futures_util::stream::StreamExt;
@@ -114,7 +114,7 @@ impl<T: SqlEntity> CompanyWithContactsCountQueryBook<T> {
 async fn test_select_by_id() {
     let pool = get_pool().await;
     let mut connection = pool.get().await.unwrap();
-    let transaction = Transaction::start(connection.transaction().await.unwrap()).await;
+    let transaction = connection.transaction().await.unwrap();
 
     let company_id = Uuid::parse_str("a7b5f2c8-8816-4c40-86bf-64e066a8db7a").unwrap();
     let query = CompanyWithContactsCountQueryBook::<CompanyShort>::new().select_by_id(&company_id);
@@ -3,14 +3,14 @@
 // Company (pommr.company)
 // ---------------------------------------------------------------------------
 
-use std::marker::PhantomData;
+use std::{fmt::Display, marker::PhantomData, sync::Arc};
 
 use agrum::{
-    DeleteQueryBook, HydrationError, InsertQueryBook, Projection, QueryBook, ReadQueryBook,
-    SqlEntity, SqlQuery, Structure, Structured, UpdateQueryBook, WhereCondition,
+    ConstraintErrorMap, DeleteQueryBook, FromCopyRow, InsertQueryBook, PrimaryKeyQueryBook,
+    QueryBook, ReadQueryBook, SqlEntity, SqlQuery, Structured, ToCopyRow, UpdateQueryBook,
+    WhereCondition,
 };
 use postgres_types::{FromSql, ToSql};
-use tokio_postgres::Row;
 use uuid::Uuid;
 
 pub const COMPANY_1_ID: &str = "a7b5f2c8-8816-4c40-86bf-64e066a8db7a";
@@ -20,45 +20,23 @@ pub const ADDRESS_2_ID: &str = "af18dfe3-b189-4d80-bbc9-a90792d92143";
 pub const CONTACT_1_ID: &str = "529fb920-6df7-4637-8f7f-0878ee140a0f";
 pub const CONTACT_2_ID: &str = "99c4996c-b5a7-42bf-af8a-2df326722566";
 
-#[derive(Debug, FromSql, ToSql)]
+#[derive(Debug, FromSql, ToSql, SqlEntity, Structured, FromCopyRow)]
 #[postgres(name = "company")]
 pub struct Company {
+    #[agrum(primary_key)]
     pub company_id: Uuid,
     pub name: String,
     pub default_address_id: Uuid,
 }
 
-impl SqlEntity for Company {
-    fn get_projection() -> Projection<Company> {
-        Projection::default()
-    }
-
-    fn hydrate(row: &Row) -> Result<Self, HydrationError> {
-        Ok(Self {
-            company_id: row.get("company_id"),
-            name: row.get("name"),
-            default_address_id: row.get("default_address_id"),
-        })
-    }
-}
-
-impl Structured for Company {
-    fn get_structure() -> Structure {
-        Structure::new(&[
-            ("company_id", "uuid"),
-            ("name", "text"),
-            ("default_address_id", "uuid"),
-        ])
-    }
-}
-
 // ---------------------------------------------------------------------------
 // Address (pommr.address)
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, FromSql, ToSql)]
+#[derive(Debug, FromSql, ToSql, SqlEntity, Structured)]
 #[postgres(name = "address")]
 pub struct Address {
+    #[agrum(primary_key)]
     pub address_id: Uuid,
     pub label: String,
     pub company_id: Uuid,
@@ -68,45 +46,14 @@ pub struct Address {
     pub associated_contact_id: Option<Uuid>,
 }
 
-impl SqlEntity for Address {
-    fn get_projection() -> Projection<Address> {
-        Projection::default()
-    }
-
-    fn hydrate(row: &Row) -> Result<Self, HydrationError> {
-        Ok(Self {
-            address_id: row.get("address_id"),
-            label: row.get("label"),
-            company_id: row.get("company_id"),
-            content: row.get("content"),
-            zipcode: row.get("zipcode"),
-            city: row.get("city"),
-            associated_contact_id: row.get("associated_contact_id"),
-        })
-    }
-}
-
-impl Structured for Address {
-    fn get_structure() -> Structure {
-        Structure::new(&[
-            ("address_id", "uuid"),
-            ("label", "text"),
-            ("company_id", "uuid"),
-            ("content", "text"),
-            ("zipcode", "text"),
-            ("city", "text"),
-            ("associated_contact_id", "uuid"),
-        ])
-    }
-}
-
 // ---------------------------------------------------------------------------
 // Contact (pommr.contact)
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, FromSql, ToSql)]
+#[derive(Debug, FromSql, ToSql, SqlEntity, Structured, ToCopyRow, FromCopyRow)]
 #[postgres(name = "contact")]
 pub struct Contact {
+    #[agrum(primary_key)]
     pub contact_id: Uuid,
     pub name: String,
     pub email: Option<String>,
@@ -114,34 +61,6 @@ pub struct Contact {
     pub company_id: Uuid,
 }
 
-impl SqlEntity for Contact {
-    fn get_projection() -> Projection<Contact> {
-        Projection::default()
-    }
-
-    fn hydrate(row: &Row) -> Result<Self, HydrationError> {
-        Ok(Self {
-            contact_id: row.get("contact_id"),
-            name: row.get("name"),
-            email: row.get("email"),
-            phone_number: row.get("phone_number"),
-            company_id: row.get("company_id"),
-        })
-    }
-}
-
-impl Structured for Contact {
-    fn get_structure() -> Structure {
-        Structure::new(&[
-            ("contact_id", "uuid"),
-            ("name", "text"),
-            ("email", "text"),
-            ("phone_number", "text"),
-            ("company_id", "uuid"),
-        ])
-    }
-}
-
 /* ---------------------------------------------------------------------------
  * AddressQueryBook
  * --------------------------------------------------------------------------- */
@@ -172,6 +91,7 @@ impl<T: SqlEntity> AddressQueryBook<T> {
 impl<T: SqlEntity> ReadQueryBook<T> for AddressQueryBook<T> {}
 impl<T: SqlEntity> InsertQueryBook<T> for AddressQueryBook<T> {}
 impl<T: SqlEntity> UpdateQueryBook<T> for AddressQueryBook<T> {}
+impl<T: SqlEntity> PrimaryKeyQueryBook<T> for AddressQueryBook<T> {}
 
 /* ---------------------------------------------------------------------------
  * CompanyQueryBook
@@ -203,6 +123,32 @@ impl<T: SqlEntity> CompanyQueryBook<T> {
 impl<T: SqlEntity> ReadQueryBook<T> for CompanyQueryBook<T> {}
 impl<T: SqlEntity> UpdateQueryBook<T> for CompanyQueryBook<T> {}
 impl<T: SqlEntity> InsertQueryBook<T> for CompanyQueryBook<T> {}
+impl<T: SqlEntity> PrimaryKeyQueryBook<T> for CompanyQueryBook<T> {}
+
+/* ---------------------------------------------------------------------------
+ * ContactError
+ * --------------------------------------------------------------------------- */
+#[derive(Debug, PartialEq)]
+pub enum ContactError {
+    /// The contact is the default contact of an address.
+    IsDefaultContact,
+
+    /// The company of the contact does not exist.
+    UnknownCompany,
+}
+
+impl Display for ContactError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IsDefaultContact => {
+                write!(f, "The contact is the default contact of an address.")
+            }
+            Self::UnknownCompany => write!(f, "The company of the contact does not exist."),
+        }
+    }
+}
+
+impl std::error::Error for ContactError {}
 
 /* ---------------------------------------------------------------------------
  * ContactQueryBook
@@ -223,8 +169,22 @@ impl<T: SqlEntity> QueryBook<T> for ContactQueryBook<T> {
     fn get_sql_source(&self) -> &'static str {
         "pommr.contact"
     }
+
+    fn get_constraint_error_map(&self) -> Option<Arc<ConstraintErrorMap>> {
+        Some(Arc::new(
+            ConstraintErrorMap::new()
+                .on_constraint("address_have_one_default_contact", |_| {
+                    ContactError::IsDefaultContact
+                })
+                .on_constraint("each_contact_belongs_to_one_company", |_| {
+                    ContactError::UnknownCompany
+                }),
+        ))
+    }
 }
 
+impl<T: SqlEntity> ReadQueryBook<T> for ContactQueryBook<T> {}
 impl<T: SqlEntity> InsertQueryBook<T> for ContactQueryBook<T> {}
 impl<T: SqlEntity> DeleteQueryBook<T> for ContactQueryBook<T> {}
 impl<T: SqlEntity> UpdateQueryBook<T> for ContactQueryBook<T> {}
+impl<T: SqlEntity> PrimaryKeyQueryBook<T> for ContactQueryBook<T> {}
@@ -0,0 +1,104 @@
+use futures_util::stream::StreamExt;
+
+use agrum::{
+    Error, IsolationLevel, ReadQueryBook, Result, RetryPolicy, SqlQuery, Transaction,
+    TransactionOptions, WhereCondition, run_in_transaction,
+};
+
+mod model;
+use model::*;
+
+mod pool;
+use pool::get_pool;
+
+/// Raise a serialization failure as a concurrent serializable transaction would.
+async fn fail_serialization(transaction: &Transaction<'_>) -> Result<()> {
+    let query = SqlQuery::<Company>::new(
+        "do $$ begin raise exception 'could not serialize access' using errcode = 'serialization_failure'; end $$",
+    );
+    let mut stream = transaction.query(query).await?;
+
+    while let Some(result) = stream.next().await {
+        result?;
+    }
+
+    Ok(())
+}
+
+async fn count_companies(transaction: &Transaction<'_>) -> Result<usize> {
+    let query = CompanyQueryBook::<Company>::default().select(WhereCondition::default());
+    let results = transaction.query(query).await?.collect::<Vec<_>>().await;
+
+    Ok(results.len())
+}
+
+fn options(max_attempts: u32) -> TransactionOptions {
+    TransactionOptions::new()
+        .isolation_level(IsolationLevel::Serializable)
+        .retry_policy(RetryPolicy::new(max_attempts))
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_retry_on_serialization_failure() {
+    let pool = get_pool().await;
+    let mut attempts = 0;
+
+    let count = run_in_transaction(&pool, &options(3), async |transaction| {
+        attempts += 1;
+
+        if attempts < 3 {
+            fail_serialization(transaction).await?;
+        }
+
+        count_companies(transaction).await
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(attempts, 3);
+    assert_eq!(count, 2);
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_retries_exhausted() {
+    let pool = get_pool().await;
+    let mut attempts = 0;
+
+    let error = run_in_transaction(&pool, &options(2), async |transaction| {
+        attempts += 1;
+        fail_serialization(transaction).await
+    })
+    .await
+    .unwrap_err();
+
+    assert_eq!(attempts, 2);
+    let Error::RetriesExhausted {
+        attempts,
+        last_error,
+    } = error
+    else {
+        panic!("expected exhausted retries, got «{error}»");
+    };
+    assert_eq!(attempts, 2);
+    assert!(last_error.is_retryable());
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_no_retry_on_other_errors() {
+    let pool = get_pool().await;
+    let mut attempts = 0;
+
+    let error = run_in_transaction(&pool, &options(3), async |transaction| {
+        attempts += 1;
+        let query = SqlQuery::<Company>::new("select 1 / 0");
+        transaction.query(query).await?.next().await.unwrap()
+    })
+    .await
+    .unwrap_err();
+
+    assert_eq!(attempts, 1);
+    assert_eq!(error.code().map(|code| code.code()), Some("22012"));
+}
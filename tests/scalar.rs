@@ -0,0 +1,57 @@
+use uuid::Uuid;
+
+use agrum::{SqlQuery, params};
+
+mod model;
+use model::*;
+
+mod pool;
+use pool::get_pool;
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_scalar_queries() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+    let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
+
+    let mut query =
+        SqlQuery::<i64>::new("select count(*) from pommr.contact where company_id = $?");
+    query.set_parameters(params![company_id]);
+    assert_eq!(transaction.query_one(query).await.unwrap(), 1);
+
+    let mut query = SqlQuery::<Uuid>::new("select company_id from pommr.company where name = $?");
+    query.set_parameters(params!["first"]);
+    assert_eq!(transaction.query_one(query).await.unwrap(), company_id);
+
+    let query = SqlQuery::<Option<String>>::new("select null::text");
+    assert_eq!(transaction.query_one(query).await.unwrap(), None);
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_tuple_queries() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+
+    let query =
+        SqlQuery::<(Uuid, String)>::new("select company_id, name from pommr.company order by name");
+    let companies = transaction.query_all(query).await.unwrap();
+    assert_eq!(
+        companies,
+        vec![
+            (Uuid::parse_str(COMPANY_1_ID).unwrap(), "first".to_string()),
+            (Uuid::parse_str(COMPANY_2_ID).unwrap(), "second".to_string()),
+        ]
+    );
+
+    let query = SqlQuery::<(String, Option<String>, i32)>::new(
+        "select name, email, 1 from pommr.contact order by name limit 1",
+    );
+    let (_name, _email, one) = transaction.query_one(query).await.unwrap();
+    assert_eq!(one, 1);
+    transaction.rollback().await.unwrap();
+}
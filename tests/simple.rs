@@ -1,11 +1,13 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Not};
 
 use futures_util::stream::StreamExt;
 use uuid::Uuid;
 
 use agrum::{
-    DeleteQueryBook, InsertQueryBook, SqlQuery, ToSqlAny, Transaction, UpdateQueryBook,
-    WhereCondition,
+    ConflictAction, ConflictTarget, Cursor, DeleteQueryBook, Error, InsertQueryBook, Keyset,
+    OwnedSqlQuery, OwnedWhereCondition, Page, PrimaryKeyQueryBook, QueryBook, ReadQueryBook,
+    SelectOptions, SortOrder, SqlCommand, SqlQuery, ToSqlAny, UpdateQueryBook, Updates,
+    WhereCondition, WithQuery, escape_like, owned_params, params,
 };
 
 mod model;
@@ -23,7 +25,7 @@ use pool::get_pool;
 async fn test_address_query_book() {
     let pool = get_pool().await;
     let mut connection = pool.get().await.unwrap();
-    let transaction = Transaction::start(connection.transaction().await.unwrap()).await;
+    let transaction = connection.transaction().await.unwrap();
 
     let query: SqlQuery<'_, Address> = AddressQueryBook::<Address>::default().get_all();
     let results = transaction
@@ -65,17 +67,10 @@ async fn test_address_query_book() {
 async fn test_condition_company_id() {
     let pool = get_pool().await;
     let mut connection = pool.get().await.unwrap();
-    let transaction = Transaction::start(connection.transaction().await.unwrap()).await;
+    let transaction = connection.transaction().await.unwrap();
     let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
     let query = CompanyQueryBook::<Company>::default().get_from_id(&company_id);
-    let company = transaction
-        .query(query)
-        .await
-        .unwrap()
-        .next()
-        .await
-        .unwrap()
-        .unwrap();
+    let company = transaction.query_one(query).await.unwrap();
     assert_eq!(company.company_id, Uuid::parse_str(COMPANY_1_ID).unwrap());
     assert_eq!(company.name, "first");
     assert_eq!(
@@ -94,7 +89,7 @@ async fn test_condition_company_id() {
 async fn test_scenario_create_company() {
     let pool = get_pool().await;
     let mut connection = pool.get().await.unwrap();
-    let transaction = Transaction::start(connection.transaction().await.unwrap()).await;
+    let transaction = connection.transaction().await.unwrap();
 
     let company_query_book = CompanyQueryBook::<Company>::default();
     let default_address_id = Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap();
@@ -102,14 +97,7 @@ async fn test_scenario_create_company() {
         ("name", &"test_name" as &dyn ToSqlAny),
         ("default_address_id", &default_address_id),
     ]));
-    let company = transaction
-        .query(query)
-        .await
-        .unwrap()
-        .next()
-        .await
-        .unwrap()
-        .unwrap();
+    let company = transaction.query_one(query).await.unwrap();
 
     let address_query_book = AddressQueryBook::<Address>::default();
     let query = address_query_book.insert(HashMap::from([
@@ -119,27 +107,13 @@ async fn test_scenario_create_company() {
         ("city", &"test_city"),
         ("company_id", &company.company_id),
     ]));
-    let address = transaction
-        .query(query)
-        .await
-        .unwrap()
-        .next()
-        .await
-        .unwrap()
-        .unwrap();
+    let address = transaction.query_one(query).await.unwrap();
 
     let query = company_query_book.update(
         HashMap::from([("default_address_id", &address.address_id as &dyn ToSqlAny)]),
         WhereCondition::new("company_id = $?", vec![&company.company_id]),
     );
-    let company = transaction
-        .query(query)
-        .await
-        .unwrap()
-        .next()
-        .await
-        .unwrap()
-        .unwrap();
+    let company = transaction.query_one(query).await.unwrap();
     assert_eq!(company.default_address_id, address.address_id);
 
     // ↓ uncomment to commit the transaction and see the changes in the database
@@ -157,7 +131,7 @@ async fn test_scenario_create_company() {
 async fn test_scenario_create_contact() {
     let pool = get_pool().await;
     let mut connection = pool.get().await.unwrap();
-    let transaction = Transaction::start(connection.transaction().await.unwrap()).await;
+    let transaction = connection.transaction().await.unwrap();
 
     let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
     let address_id = Uuid::parse_str(ADDRESS_1_ID).unwrap();
@@ -169,14 +143,7 @@ async fn test_scenario_create_contact() {
         ("phone_number", &"test_phone_number"),
         ("company_id", &company_id),
     ]));
-    let contact = transaction
-        .query(query)
-        .await
-        .unwrap()
-        .next()
-        .await
-        .unwrap()
-        .unwrap();
+    let contact = transaction.query_one(query).await.unwrap();
 
     let query = AddressQueryBook::<Address>::default().update(
         HashMap::from([(
@@ -185,27 +152,567 @@ async fn test_scenario_create_contact() {
         )]),
         WhereCondition::new("address_id = $?", vec![&address_id as &dyn ToSqlAny]),
     );
-    let address = transaction
-        .query(query)
-        .await
-        .unwrap()
-        .next()
-        .await
-        .unwrap()
-        .unwrap();
+    let address = transaction.query_one(query).await.unwrap();
     assert_eq!(address.associated_contact_id, Some(contact.contact_id));
 
     let query = contact_query_book.delete(WhereCondition::new(
         "contact_id = $?",
         vec![&contact.contact_id],
     ));
-    let _result = transaction
-        .query(query)
+    let error = transaction.query_one(query).await.unwrap_err();
+    let Error::Domain(error) = error else {
+        panic!("expected a domain error, got «{error}»");
+    };
+    assert_eq!(
+        Some(&ContactError::IsDefaultContact),
+        error.downcast_ref::<ContactError>()
+    );
+    transaction.rollback().await.unwrap();
+}
+
+// The primary key query book finds, updates and deletes entities by their
+// primary key.
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_primary_key_query_book() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+
+    let company_id = Uuid::parse_str(COMPANY_2_ID).unwrap();
+    let contact_query_book = ContactQueryBook::<Contact>::default();
+    let query = contact_query_book.insert(HashMap::from([
+        ("name", &"test_name" as &dyn ToSqlAny),
+        ("company_id", &company_id),
+    ]));
+    let contact_id = transaction.query_one(query).await.unwrap().contact_id;
+    let contact = transaction
+        .query_one(contact_query_book.find_by_pk(vec![&contact_id]))
         .await
-        .unwrap()
-        .next()
+        .unwrap();
+    assert_eq!(contact.name, "test_name");
+
+    let query = contact_query_book.update_by_pk(
+        HashMap::from([("name", &"new_name" as &dyn ToSqlAny)]),
+        vec![&contact_id],
+    );
+    let contact = transaction.query_one(query).await.unwrap();
+    assert_eq!(contact.contact_id, contact_id);
+    assert_eq!(contact.name, "new_name");
+
+    let contact = transaction
+        .query_one(contact_query_book.delete_by_pk(vec![&contact_id]))
+        .await
+        .unwrap();
+    assert_eq!(contact.contact_id, contact_id);
+    let contact = transaction
+        .query_opt(contact_query_book.find_by_pk(vec![&contact_id]))
+        .await
+        .unwrap();
+    assert!(contact.is_none());
+    transaction.rollback().await.unwrap();
+}
+
+// Upserting an existing contact updates it in place and returns the updated
+// entity.
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_upsert_contact() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+
+    let contact_id = Uuid::parse_str(CONTACT_1_ID).unwrap();
+    let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
+    let query = ContactQueryBook::<Contact>::default().upsert(
+        HashMap::from([
+            ("contact_id", &contact_id as &dyn ToSqlAny),
+            ("name", &"new_name"),
+            ("company_id", &company_id),
+        ]),
+        ConflictTarget::Columns(vec!["contact_id"]),
+        ConflictAction::UpdateAll,
+    );
+    let contact = transaction.query_one(query).await.unwrap();
+    assert_eq!(contact.contact_id, contact_id);
+    assert_eq!(contact.name, "new_name");
+    assert_eq!(contact.email.as_deref(), Some("thierrywutz@first.fr"));
+    transaction.rollback().await.unwrap();
+}
+
+// Several contacts are inserted with a single statement.
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_insert_many_contacts() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+
+    let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
+    let queries = ContactQueryBook::<Contact>::default().insert_many(vec![
+        HashMap::from([
+            ("name", &"first_name" as &dyn ToSqlAny),
+            ("company_id", &company_id),
+        ]),
+        HashMap::from([
+            ("name", &"second_name" as &dyn ToSqlAny),
+            ("email", &"second@email.com"),
+            ("company_id", &company_id),
+        ]),
+    ]);
+    assert_eq!(queries.len(), 1);
+
+    let mut contacts = Vec::new();
+    for query in queries {
+        contacts.extend(transaction.query_all(query).await.unwrap());
+    }
+    assert_eq!(contacts.len(), 2);
+    assert_eq!(contacts[0].name, "first_name");
+    assert_eq!(contacts[0].email, None);
+    assert_eq!(contacts[1].email.as_deref(), Some("second@email.com"));
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_execute_commands() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+
+    let affected = transaction
+        .execute(SqlCommand::new(
+            "create temporary table imported_contact (name text not null)",
+        ))
+        .await
+        .unwrap();
+    assert_eq!(affected, 0);
+
+    let mut command = SqlCommand::new("insert into {:source:} (name) values ($?), ($?)");
+    command
+        .set_variable("source", "imported_contact")
+        .set_parameters(params!["first", "second"]);
+    assert_eq!(transaction.execute(command).await.unwrap(), 2);
+
+    let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
+    let contact_book = ContactQueryBook::<Contact>::default();
+    let query = contact_book.insert(HashMap::from([
+        ("name", &"to delete" as &dyn ToSqlAny),
+        ("company_id", &company_id),
+    ]));
+    assert_eq!(transaction.execute(query).await.unwrap(), 1);
+
+    let query = contact_book.delete(WhereCondition::new("name = $?", params!["to delete"]));
+    assert_eq!(transaction.execute(query).await.unwrap(), 1);
+
+    // the constraint error map of the query book is used
+    let contact_id = Uuid::parse_str(CONTACT_1_ID).unwrap();
+    let error = transaction
+        .execute(contact_book.delete_by_pk(vec![&contact_id]))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::Domain(_)));
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_query_helpers() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+    let company_book = CompanyQueryBook::<Company>::default();
+
+    let companies = transaction
+        .query_all(company_book.select(WhereCondition::default()))
+        .await
+        .unwrap();
+    assert_eq!(companies.len(), 2);
+
+    let error = transaction
+        .query_one(company_book.select(WhereCondition::default()))
         .await
-        .unwrap()
         .unwrap_err();
+    assert!(matches!(error, Error::TooManyRows));
+
+    let error = transaction
+        .query_opt(company_book.select(WhereCondition::default()))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::TooManyRows));
+
+    let unknown_id = Uuid::new_v4();
+    let company = transaction
+        .query_opt(company_book.find_by_pk(vec![&unknown_id]))
+        .await
+        .unwrap();
+    assert!(company.is_none());
+
+    let error = transaction
+        .query_one(company_book.find_by_pk(vec![&unknown_id]))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, Error::NoRows));
+
+    let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
+    let company = transaction
+        .query_opt(company_book.find_by_pk(vec![&company_id]))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(company.name, "first");
+    transaction.rollback().await.unwrap();
+}
+
+fn contacts_of(company_id: Uuid) -> OwnedSqlQuery<Contact> {
+    let (condition, parameters) =
+        OwnedWhereCondition::new("company_id = $?", owned_params![company_id]).expand();
+    let mut query =
+        OwnedSqlQuery::new("select {:projection:} from pommr.contact where {:condition:}");
+    query
+        .set_variable("condition", &condition)
+        .set_parameters(parameters);
+
+    query
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_owned_query_in_spawned_task() {
+    let query = contacts_of(Uuid::parse_str(COMPANY_1_ID).unwrap());
+
+    let contacts = tokio::spawn(async move {
+        let pool = get_pool().await;
+        let mut connection = pool.get().await.unwrap();
+        let transaction = connection.transaction().await.unwrap();
+        let contacts = transaction.query_all(&query).await.unwrap();
+        transaction.rollback().await.unwrap();
+
+        contacts
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(contacts.len(), 1);
+    assert_eq!(
+        contacts[0].contact_id,
+        Uuid::parse_str(CONTACT_1_ID).unwrap()
+    );
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_into_owned_query_in_spawned_task() {
+    let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
+    let query = ContactQueryBook::<Contact>::default()
+        .select(WhereCondition::new("company_id = $?", params![company_id]))
+        .into_owned();
+
+    let contacts = tokio::spawn(async move {
+        let pool = get_pool().await;
+        let mut connection = pool.get().await.unwrap();
+        let transaction = connection.transaction().await.unwrap();
+        let contacts = transaction.query_all(&query).await.unwrap();
+        transaction.rollback().await.unwrap();
+
+        contacts
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(contacts.len(), 1);
+    assert_eq!(
+        contacts[0].contact_id,
+        Uuid::parse_str(CONTACT_1_ID).unwrap()
+    );
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_named_parameters() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+    let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
+    let mut query = SqlQuery::<Company>::new(
+        "select {:projection:} from pommr.company where company_id = $:company_id and name = $? and company_id in (select company_id from pommr.contact where company_id = $:company_id)",
+    );
+    query
+        .set_named_parameter("company_id", &company_id)
+        .set_parameters(params!["first"]);
+
+    let company = transaction.query_one(query).await.unwrap();
+    assert_eq!(company.company_id, company_id);
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_update_with_expressions() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+    let company_book = CompanyQueryBook::<Company>::default();
+    let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
+
+    let updates = Updates::new().set_expression_with("name", "upper(name) || $?", params!["!"]);
+    let query = company_book.update(
+        updates,
+        WhereCondition::new("company_id = $?", vec![&company_id]),
+    );
+    let company = transaction.query_one(query).await.unwrap();
+    assert_eq!(company.name, "FIRST!");
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_condition_combinators() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+    let company_book = CompanyQueryBook::<Company>::default();
+    let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
+    let company_ids = vec![company_id, Uuid::new_v4()];
+
+    let companies = transaction
+        .query_all(company_book.select(WhereCondition::any("company_id", &company_ids)))
+        .await
+        .unwrap();
+    assert_eq!(companies.len(), 1);
+
+    let companies = transaction
+        .query_all(company_book.select(WhereCondition::any("company_id", &company_ids).not()))
+        .await
+        .unwrap();
+    assert_eq!(companies.len(), 1);
+    assert_eq!(companies[0].name, "second");
+
+    let pattern = format!("{}%", escape_like("FIR"));
+    let companies = transaction
+        .query_all(company_book.select(WhereCondition::ilike("name", &pattern)))
+        .await
+        .unwrap();
+    assert_eq!(companies.len(), 1);
+
+    let mut contacts = SqlCommand::new(
+        "select 1 from pommr.contact where contact.company_id = company.company_id and contact.name <> $?",
+    );
+    contacts.add_parameter(&"nobody");
+    let companies = transaction
+        .query_all(company_book.select(
+            WhereCondition::exists(contacts).and_where(WhereCondition::is_not_null("name")),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(companies.len(), 2);
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_subqueries() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+    let company_book = CompanyQueryBook::<Company>::default();
+    let contact_id = Uuid::parse_str(CONTACT_1_ID).unwrap();
+
+    let mut contacts =
+        SqlCommand::new("select company_id from pommr.contact where contact_id = $?");
+    contacts.add_parameter(&contact_id);
+    let companies = transaction
+        .query_all(
+            company_book.select(
+                WhereCondition::new("name <> $?", params!["nobody"])
+                    .and_where(WhereCondition::in_subquery("company_id", contacts)),
+            ),
+        )
+        .await
+        .unwrap();
+    assert_eq!(companies.len(), 1);
+    assert_eq!(
+        companies[0].company_id,
+        Uuid::parse_str(COMPANY_1_ID).unwrap()
+    );
+
+    let mut contacts =
+        SqlQuery::<Contact>::new("select {:projection:} from pommr.contact where contact_id <> $?");
+    contacts.add_parameter(&contact_id);
+    let mut query = SqlQuery::<Company>::new(
+        "select {:projection:} from pommr.company where name = $? and company_id in (select company_id from ({:contacts:}) as contact) and name <> $?",
+    );
+    query
+        .set_subquery("contacts", contacts)
+        .set_parameters(params!["second", "nobody"]);
+    let company = transaction.query_one(query).await.unwrap();
+    assert_eq!(company.name, "second");
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_with_query() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+    let company_book = CompanyQueryBook::<Company>::default();
+    let default_address_id = Uuid::parse_str("00000000-0000-0000-0000-000000000000").unwrap();
+
+    let new_company = company_book.insert(HashMap::from([
+        ("name", &"third" as &dyn ToSqlAny),
+        ("default_address_id", &default_address_id),
+    ]));
+    let mut query = SqlQuery::<Company>::new(
+        "select {:projection:} from new_company where name = $? union all select {:projection:} from pommr.company where name = $?",
+    );
+    query.set_parameters(params!["third", "first"]);
+    let query = WithQuery::new()
+        .with("new_company", new_company)
+        .select(query);
+
+    let companies = transaction.query_all(query).await.unwrap();
+    let names: Vec<&str> = companies.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["third", "first"]);
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_select_with_options() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+    let company_book = CompanyQueryBook::<Company>::default();
+
+    let companies = transaction
+        .query_all(company_book.select_with(
+            WhereCondition::default(),
+            SelectOptions::new().order_by("name", SortOrder::Desc),
+        ))
+        .await
+        .unwrap();
+    let names: Vec<&str> = companies.iter().map(|c| c.name.as_str()).collect();
+    assert_eq!(names, vec!["second", "first"]);
+
+    let companies = transaction
+        .query_all(
+            company_book.select_with(
+                WhereCondition::is_not_null("name"),
+                SelectOptions::new()
+                    .order_by("name", SortOrder::Asc)
+                    .limit(&1)
+                    .offset(&1),
+            ),
+        )
+        .await
+        .unwrap();
+    assert_eq!(companies.len(), 1);
+    assert_eq!(companies[0].name, "second");
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_paginate_after() {
+    struct ContactByNameQueryBook;
+
+    impl QueryBook<Contact> for ContactByNameQueryBook {
+        fn get_sql_source(&self) -> &'static str {
+            "pommr.contact"
+        }
+    }
+
+    impl ReadQueryBook<Contact> for ContactByNameQueryBook {
+        fn get_keyset(&self) -> Keyset {
+            Keyset::new(&["name", "contact_id"], SortOrder::Asc)
+        }
+    }
+
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection.transaction().await.unwrap();
+    transaction
+        .execute(SqlCommand::new(
+            "insert into pommr.contact (name, company_id) select contact_name, company_id from pommr.company, unnest(array['Alice', 'Bruno', 'Denis']) as contact_name where name = 'first'",
+        ))
+        .await
+        .unwrap();
+    let book = ContactByNameQueryBook;
+    let names = |page: &Page<Contact>| -> Vec<String> {
+        page.items.iter().map(|c| c.name.clone()).collect()
+    };
+
+    let first = transaction
+        .query_page(
+            book.paginate_after(WhereCondition::default(), None, &2)
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(names(&first), vec!["Alice", "Bruno"]);
+    assert!(first.previous.is_none());
+
+    let cursor = Cursor::decode(&first.next.unwrap().encode()).unwrap();
+    let second = transaction
+        .query_page(
+            book.paginate_after(WhereCondition::default(), Some(&cursor), &2)
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(names(&second), vec!["Caroline Pagan", "Denis"]);
+
+    let last = transaction
+        .query_page(
+            book.paginate_after(WhereCondition::default(), second.next.as_ref(), &2)
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(names(&last), vec!["Thierry Wütz"]);
+    assert!(last.next.is_none());
+
+    let back = transaction
+        .query_page(
+            book.paginate_after(WhereCondition::default(), last.previous.as_ref(), &2)
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(names(&back), vec!["Caroline Pagan", "Denis"]);
+    let back = transaction
+        .query_page(
+            book.paginate_after(WhereCondition::default(), back.previous.as_ref(), &2)
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(names(&back), vec!["Alice", "Bruno"]);
+    assert!(back.previous.is_none());
+    assert!(back.next.is_some());
+
+    let company_id = Uuid::parse_str(COMPANY_2_ID).unwrap();
+    let contacts = transaction
+        .query_page(
+            ContactQueryBook::<Contact>::default()
+                .paginate_after(
+                    WhereCondition::new("company_id = $?", params![company_id]),
+                    None,
+                    &10,
+                )
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(names(&contacts), vec!["Caroline Pagan"]);
+    assert!(contacts.next.is_none());
+
+    let result = ContactQueryBook::<Contact>::default().paginate_after(
+        WhereCondition::default(),
+        Some(&cursor),
+        &2,
+    );
+    assert!(matches!(result, Err(Error::InvalidCursor(_))));
     transaction.rollback().await.unwrap();
 }
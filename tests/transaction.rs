@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use futures_util::stream::StreamExt;
+use uuid::Uuid;
+
+use agrum::{
+    Error, InsertQueryBook, IsolationLevel, PrimaryKeyQueryBook, ReadQueryBook, SqlQuery, ToSqlAny,
+    Transaction, WhereCondition,
+};
+
+mod model;
+use model::*;
+
+mod pool;
+use pool::get_pool;
+
+async fn current_setting(transaction: &Transaction<'_>, name: &'static str) -> String {
+    let mut query = SqlQuery::<String>::new("select current_setting($?)");
+    query.add_parameter(&name);
+
+    transaction.query_one(query).await.unwrap()
+}
+
+async fn count_contacts(transaction: &Transaction<'_>) -> usize {
+    transaction
+        .query(ContactQueryBook::<Contact>::default().select(WhereCondition::default()))
+        .await
+        .unwrap()
+        .collect::<Vec<_>>()
+        .await
+        .len()
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_savepoint_rollback() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let mut transaction = connection.transaction().await.unwrap();
+
+    let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
+    let savepoint = transaction.savepoint("before_insert").await.unwrap();
+    let query = ContactQueryBook::<Contact>::default().insert(HashMap::from([
+        ("name", &"savepoint contact" as &dyn ToSqlAny),
+        ("company_id", &company_id),
+    ]));
+    savepoint
+        .query(query)
+        .await
+        .unwrap()
+        .next()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(count_contacts(&savepoint).await, 3);
+    savepoint.rollback().await.unwrap();
+
+    assert_eq!(count_contacts(&transaction).await, 2);
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_nested_release() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let mut transaction = connection.transaction().await.unwrap();
+
+    let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
+    let contact = transaction
+        .nested(async |transaction| {
+            let query = ContactQueryBook::<Contact>::default().insert(HashMap::from([
+                ("name", &"nested contact" as &dyn ToSqlAny),
+                ("company_id", &company_id),
+            ]));
+            transaction.query(query).await?.next().await.unwrap()
+        })
+        .await
+        .unwrap();
+    assert_eq!(contact.name, "nested contact");
+
+    assert_eq!(count_contacts(&transaction).await, 3);
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_nested_rollback_on_error() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let mut transaction = connection.transaction().await.unwrap();
+
+    let contact_id = Uuid::parse_str(CONTACT_1_ID).unwrap();
+    let result = transaction
+        .nested(async |transaction| {
+            let query = ContactQueryBook::<Contact>::default().delete_by_pk(vec![&contact_id]);
+            transaction.query(query).await?.next().await.unwrap()
+        })
+        .await;
+    let Err(Error::Domain(error)) = result else {
+        panic!("expected a domain error");
+    };
+    assert_eq!(
+        Some(&ContactError::IsDefaultContact),
+        error.downcast_ref::<ContactError>()
+    );
+
+    // the outer transaction is still usable
+    assert_eq!(count_contacts(&transaction).await, 2);
+    transaction.rollback().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "skipping database tests"]
+async fn test_build_transaction() {
+    let pool = get_pool().await;
+    let mut connection = pool.get().await.unwrap();
+    let transaction = connection
+        .build_transaction()
+        .isolation_level(IsolationLevel::Serializable)
+        .read_only(true)
+        .deferrable(true)
+        .set_local("statement_timeout", "5s")
+        .start()
+        .await
+        .unwrap();
+
+    assert_eq!(
+        current_setting(&transaction, "transaction_isolation").await,
+        "serializable"
+    );
+    assert_eq!(
+        current_setting(&transaction, "transaction_read_only").await,
+        "on"
+    );
+    assert_eq!(
+        current_setting(&transaction, "statement_timeout").await,
+        "5s"
+    );
+
+    let company_id = Uuid::parse_str(COMPANY_1_ID).unwrap();
+    let query = ContactQueryBook::<Contact>::default().insert(HashMap::from([
+        ("name", &"read only contact" as &dyn ToSqlAny),
+        ("company_id", &company_id),
+    ]));
+    let error = transaction
+        .query(query)
+        .await
+        .unwrap()
+        .next()
+        .await
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(error.code().map(|code| code.code()), Some("25006"));
+    transaction.rollback().await.unwrap();
+
+    // local settings do not leak out of the transaction
+    let transaction = connection.build_transaction().start().await.unwrap();
+    assert_eq!(
+        current_setting(&transaction, "statement_timeout").await,
+        "0"
+    );
+    transaction.rollback().await.unwrap();
+}